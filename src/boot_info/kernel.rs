@@ -1,22 +1,170 @@
+use core::mem::{self, MaybeUninit};
+
 use time::OffsetDateTime;
 
 use super::{
-    BootInfo, HardwareInfo, LoadInfo, PlatformInfo, RawBootInfo, RawHardwareInfo, RawLoadInfo,
-    RawPlatformInfo, TlsInfo,
+    Arguments, BootInfo, BootInfoField, HardwareInfo, IRelativeRelocation, Initrd,
+    InvalidBootInfoError, InvalidBootInfoErrorKind, LoadInfo, MemoryRegion, PlatformInfo,
+    RawArguments, RawBootInfo, RawBootInfoHeader, RawHardwareInfo, RawLoadInfo, RawPlatformInfo,
+    RawSlice, StrList, TlsInfo,
 };
 
-impl From<RawHardwareInfo> for HardwareInfo {
-    fn from(raw_hardware_info: RawHardwareInfo) -> Self {
-        Self {
-            phys_addr_range: raw_hardware_info.phys_addr_start..raw_hardware_info.phys_addr_end,
+impl RawBootInfoHeader {
+    fn validate(&self) -> Result<(), InvalidBootInfoError> {
+        if self.magic != Self::MAGIC {
+            return Err(InvalidBootInfoErrorKind::InvalidMagic { found: self.magic }.into());
+        }
+
+        let sum = [self.entry_version, self.size, self.header_checksum]
+            .into_iter()
+            .fold(self.magic, u32::wrapping_add);
+        if sum != 0 {
+            return Err(InvalidBootInfoErrorKind::ChecksumMismatch.into());
+        }
+
+        if self.entry_version != u32::from(crate::HERMIT_ENTRY_VERSION) {
+            return Err(InvalidBootInfoErrorKind::EntryVersionMismatch {
+                found: self.entry_version,
+            }
+            .into());
+        }
+
+        if (self.size as usize) < Self::MIN_SIZE {
+            return Err(InvalidBootInfoErrorKind::TooSmall { size: self.size }.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl RawBootInfo {
+    /// Reads the boot information from the loader.
+    ///
+    /// The loader may have been built against a version of this crate with fewer fields.
+    /// Fields missing in the loader's layout are read as empty.
+    /// Fields unknown to this version of the crate are ignored.
+    ///
+    /// Convert the result into [`BootInfo`] using [`TryFrom`].
+    ///
+    /// # Safety
+    ///
+    /// `raw_boot_info` must point to a [`RawBootInfo`] from the loader.
+    /// It must be readable for the size in its header if the header is valid.
+    pub unsafe fn read(raw_boot_info: *const Self) -> Result<Self, InvalidBootInfoError> {
+        // SAFETY: The header has not changed since its introduction.
+        let header = unsafe { raw_boot_info.cast::<RawBootInfoHeader>().read_unaligned() };
+        header.validate()?;
+
+        let size = (header.size as usize).min(mem::size_of::<Self>());
+        let mut uninit = MaybeUninit::<Self>::zeroed();
+        // SAFETY: The caller guarantees that `size` bytes are readable.
+        // Fields after `RawBootInfoHeader::MIN_SIZE` are valid when zeroed.
+        unsafe {
+            raw_boot_info
+                .cast::<u8>()
+                .copy_to_nonoverlapping(uninit.as_mut_ptr().cast::<u8>(), size);
+            Ok(uninit.assume_init())
+        }
+    }
+}
+
+impl<T> RawSlice<T> {
+    fn into_slice(self, field: BootInfoField) -> Result<&'static [T], InvalidBootInfoError> {
+        if self.data.is_null() {
+            if self.len != 0 {
+                return Err(InvalidBootInfoErrorKind::NullPointer(field).into());
+            }
+            return Ok(&[]);
+        }
+
+        if !self.data.is_aligned() {
+            return Err(InvalidBootInfoErrorKind::Misaligned(field).into());
+        }
+
+        let size = usize::try_from(self.len)
+            .ok()
+            .and_then(|len| len.checked_mul(mem::size_of::<T>()));
+        if size.is_none_or(|size| size > isize::MAX as usize) {
+            return Err(InvalidBootInfoErrorKind::TooLarge(field).into());
+        }
+
+        // SAFETY: The data is valid forever.
+        let slice = unsafe { core::slice::from_raw_parts(self.data, self.len as usize) };
+        Ok(slice)
+    }
+}
+
+impl RawSlice<u8> {
+    fn into_str(self, field: BootInfoField) -> Result<Option<&'static str>, InvalidBootInfoError> {
+        if self.data.is_null() && self.len == 0 {
+            return Ok(None);
+        }
+
+        let s = core::str::from_utf8(self.into_slice(field)?)
+            .map_err(|_| InvalidBootInfoErrorKind::NotUtf8(field))?;
+        Ok(Some(s))
+    }
+
+    fn into_str_list(self, field: BootInfoField) -> Result<StrList, InvalidBootInfoError> {
+        let buf = self.into_str(field)?.unwrap_or_default();
+        let str_list =
+            StrList::new(buf).ok_or(InvalidBootInfoErrorKind::NotNulTerminated(field))?;
+        Ok(str_list)
+    }
+}
+
+impl TryFrom<RawArguments> for Arguments {
+    type Error = InvalidBootInfoError;
+
+    fn try_from(raw_arguments: RawArguments) -> Result<Self, Self::Error> {
+        Ok(Self {
+            kernel_args: raw_arguments
+                .kernel_args
+                .into_str_list(BootInfoField::KernelArgs)?,
+            app_args: raw_arguments
+                .app_args
+                .into_str_list(BootInfoField::AppArgs)?,
+            env_vars: raw_arguments
+                .env_vars
+                .into_str_list(BootInfoField::EnvVars)?,
+        })
+    }
+}
+
+impl HardwareInfo {
+    fn from_raw(
+        raw_hardware_info: RawHardwareInfo,
+        memory_regions: &'static [MemoryRegion],
+    ) -> Result<Self, InvalidBootInfoError> {
+        let phys_addr_range = raw_hardware_info.phys_addr_start..raw_hardware_info.phys_addr_end;
+        if phys_addr_range.is_empty() {
+            return Err(InvalidBootInfoErrorKind::EmptyPhysAddrRange.into());
+        }
+
+        if memory_regions
+            .iter()
+            .any(|memory_region| memory_region.start > memory_region.end)
+        {
+            return Err(
+                InvalidBootInfoErrorKind::InvalidRange(BootInfoField::MemoryRegions).into(),
+            );
+        }
+
+        Ok(Self {
+            phys_addr_range,
             serial_port_base: raw_hardware_info.serial_port_base,
             device_tree: raw_hardware_info.device_tree,
-        }
+            memory_regions,
+        })
     }
 }
 
-impl From<RawLoadInfo> for LoadInfo {
-    fn from(raw_load_info: RawLoadInfo) -> Self {
+impl LoadInfo {
+    fn from_raw(
+        raw_load_info: RawLoadInfo,
+        initrds: &'static [Initrd],
+        irelative_relocations: &'static [IRelativeRelocation],
+    ) -> Result<Self, InvalidBootInfoError> {
         let TlsInfo {
             start,
             filesz,
@@ -24,37 +172,62 @@ impl From<RawLoadInfo> for LoadInfo {
             align,
         } = raw_load_info.tls_info;
 
-        Self {
+        for initrd in initrds {
+            if initrd.start > initrd.end {
+                return Err(InvalidBootInfoErrorKind::InvalidRange(BootInfoField::Initrds).into());
+            }
+            initrd.name.into_str(BootInfoField::InitrdName)?;
+        }
+
+        Ok(Self {
             kernel_image_addr_range: raw_load_info.kernel_image_addr_start
                 ..raw_load_info.kernel_image_addr_end,
             tls_info: (start != 0 || filesz != 0 || memsz != 0 || align != 0)
                 .then_some(raw_load_info.tls_info),
+            initrds,
+            irelative_relocations,
+        })
+    }
+}
+
+impl IRelativeRelocation {
+    /// Calls the resolver and stores the returned value at [`Self::addr`].
+    ///
+    /// # Safety
+    ///
+    /// This relocation must be from [`LoadInfo::irelative_relocations`] of the running kernel.
+    /// The resolver must be safe to call at this point, and [`Self::addr`] must be writable.
+    pub unsafe fn apply(&self) {
+        // SAFETY: The caller guarantees that `resolver` is a resolver function of the running kernel.
+        let resolver =
+            unsafe { mem::transmute::<usize, extern "C" fn() -> u64>(self.resolver as usize) };
+        let value = resolver();
+
+        // SAFETY: The caller guarantees that `addr` is writable.
+        unsafe {
+            (self.addr as *mut u64).write_unaligned(value);
         }
     }
 }
 
-impl From<RawPlatformInfo> for PlatformInfo {
-    fn from(raw_platform_info: RawPlatformInfo) -> Self {
-        match raw_platform_info {
+impl TryFrom<RawPlatformInfo> for PlatformInfo {
+    type Error = InvalidBootInfoError;
+
+    fn try_from(raw_platform_info: RawPlatformInfo) -> Result<Self, Self::Error> {
+        let platform_info = match raw_platform_info {
             #[cfg(target_arch = "x86_64")]
             RawPlatformInfo::Multiboot {
                 command_line_data,
                 command_line_len,
                 multiboot_info_addr,
-            } => {
-                let command_line = (!command_line_data.is_null()).then(|| {
-                    // SAFETY: cmdline and cmdsize are valid forever.
-                    let slice = unsafe {
-                        core::slice::from_raw_parts(command_line_data, command_line_len as usize)
-                    };
-                    core::str::from_utf8(slice).unwrap()
-                });
-
-                Self::Multiboot {
-                    command_line,
-                    multiboot_info_addr,
+            } => Self::Multiboot {
+                command_line: RawSlice {
+                    data: command_line_data,
+                    len: command_line_len,
                 }
-            }
+                .into_str(BootInfoField::CommandLine)?,
+                multiboot_info_addr,
+            },
             #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
             RawPlatformInfo::LinuxBoot => Self::LinuxBoot,
             RawPlatformInfo::Uhyve {
@@ -69,37 +242,242 @@ impl From<RawPlatformInfo> for PlatformInfo {
                 boot_time: OffsetDateTime::from_unix_timestamp_nanos(i128::from_ne_bytes(
                     boot_time.0,
                 ))
-                .unwrap(),
+                .map_err(|_| InvalidBootInfoErrorKind::BootTimeOutOfRange)?,
             },
             RawPlatformInfo::LinuxBootParams {
                 command_line_data,
                 command_line_len,
                 boot_params_addr,
-            } => {
-                let command_line = (!command_line_data.is_null()).then(|| {
-                    // SAFETY: cmdline and cmdsize are valid forever.
-                    let slice = unsafe {
-                        core::slice::from_raw_parts(command_line_data, command_line_len as usize)
-                    };
-                    core::str::from_utf8(slice).unwrap()
-                });
-
-                Self::LinuxBootParams {
-                    command_line,
-                    boot_params_addr,
+            } => Self::LinuxBootParams {
+                command_line: RawSlice {
+                    data: command_line_data,
+                    len: command_line_len,
                 }
-            }
+                .into_str(BootInfoField::CommandLine)?,
+                boot_params_addr,
+            },
             RawPlatformInfo::Fdt => Self::Fdt,
-        }
+        };
+        Ok(platform_info)
+    }
+}
+
+/// Validates and converts boot information from the loader.
+///
+/// The [`RawBootInfo`] must be obtained from [`RawBootInfo::read`].
+/// Copying it from the loader's memory otherwise is undefined behavior if the loader's layout is smaller.
+impl TryFrom<RawBootInfo> for BootInfo {
+    type Error = InvalidBootInfoError;
+
+    fn try_from(raw_boot_info: RawBootInfo) -> Result<Self, Self::Error> {
+        raw_boot_info.header.validate()?;
+
+        Ok(Self {
+            hardware_info: HardwareInfo::from_raw(
+                raw_boot_info.hardware_info,
+                raw_boot_info
+                    .memory_regions
+                    .into_slice(BootInfoField::MemoryRegions)?,
+            )?,
+            load_info: LoadInfo::from_raw(
+                raw_boot_info.load_info,
+                raw_boot_info.initrds.into_slice(BootInfoField::Initrds)?,
+                raw_boot_info
+                    .irelative_relocations
+                    .into_slice(BootInfoField::IRelativeRelocations)?,
+            )?,
+            platform_info: raw_boot_info.platform_info.try_into()?,
+            arguments: raw_boot_info.arguments.try_into()?,
+        })
     }
 }
 
-impl From<RawBootInfo> for BootInfo {
-    fn from(raw_boot_info: RawBootInfo) -> Self {
-        Self {
-            hardware_info: raw_boot_info.hardware_info.into(),
-            load_info: raw_boot_info.load_info.into(),
-            platform_info: raw_boot_info.platform_info.into(),
+#[cfg(all(test, feature = "loader"))]
+mod tests {
+    use core::num::NonZeroU64;
+
+    use super::*;
+
+    fn raw_boot_info() -> RawBootInfo {
+        BootInfo {
+            hardware_info: HardwareInfo {
+                phys_addr_range: 0..0x1000_0000,
+                serial_port_base: None,
+                device_tree: None,
+                memory_regions: &[],
+            },
+            load_info: LoadInfo {
+                kernel_image_addr_range: 0x20_0000..0x40_0000,
+                tls_info: None,
+                initrds: &[],
+                irelative_relocations: &[],
+            },
+            platform_info: PlatformInfo::LinuxBootParams {
+                command_line: Some("-freq 2000"),
+                boot_params_addr: NonZeroU64::new(0x7000).unwrap(),
+            },
+            arguments: Arguments {
+                kernel_args: StrList::new("-freq\x002000\0").unwrap(),
+                ..Default::default()
+            },
         }
+        .into()
+    }
+
+    #[test]
+    fn read() {
+        let raw_boot_info = raw_boot_info();
+        let boot_info =
+            BootInfo::try_from(unsafe { RawBootInfo::read(&raw_boot_info) }.unwrap()).unwrap();
+        assert!(boot_info.arguments.kernel_args.iter().eq(["-freq", "2000"]));
+
+        // A loader with fewer fields
+        let mut truncated = raw_boot_info;
+        truncated.header.size = RawBootInfoHeader::MIN_SIZE as u32;
+        truncated.header.header_checksum = truncated
+            .header
+            .header_checksum
+            .wrapping_add((mem::size_of::<RawBootInfo>() - RawBootInfoHeader::MIN_SIZE) as u32);
+        truncated.arguments.kernel_args = RawSlice {
+            data: core::ptr::null(),
+            len: 0xdead,
+        };
+        let boot_info =
+            BootInfo::try_from(unsafe { RawBootInfo::read(&truncated) }.unwrap()).unwrap();
+        assert_eq!(boot_info.arguments.kernel_args.iter().count(), 0);
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut raw_boot_info = raw_boot_info();
+        raw_boot_info.header.size += 8;
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(err.kind(), &InvalidBootInfoErrorKind::ChecksumMismatch);
+
+        raw_boot_info.header.magic = 0;
+        let err = unsafe { RawBootInfo::read(&raw_boot_info) }.unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::InvalidMagic { found: 0 }
+        );
+    }
+
+    #[test]
+    fn irelative_relocations() {
+        extern "C" fn resolver() -> u64 {
+            0x42
+        }
+
+        let mut target = 0u64;
+        let irelative_relocations = alloc::vec![IRelativeRelocation {
+            addr: &raw mut target as u64,
+            resolver: resolver as extern "C" fn() -> u64 as usize as u64,
+        }]
+        .leak();
+        let mut raw_boot_info = raw_boot_info();
+        raw_boot_info.irelative_relocations = (&*irelative_relocations).into();
+
+        let boot_info = BootInfo::try_from(raw_boot_info).unwrap();
+        for irelative_relocation in boot_info.load_info.irelative_relocations {
+            unsafe { irelative_relocation.apply() };
+        }
+        assert_eq!(target, 0x42);
+    }
+
+    #[test]
+    fn invalid_slices() {
+        static MEMORY_REGIONS: [MemoryRegion; 1] = [MemoryRegion {
+            start: 0x2000,
+            end: 0x1000,
+            kind: 1,
+        }];
+        let valid = raw_boot_info();
+
+        let mut raw_boot_info = valid;
+        raw_boot_info.memory_regions = (&MEMORY_REGIONS[..]).into();
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::InvalidRange(BootInfoField::MemoryRegions)
+        );
+
+        let mut raw_boot_info = valid;
+        raw_boot_info.memory_regions.data =
+            MEMORY_REGIONS.as_ptr().cast::<u8>().wrapping_add(1).cast();
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::Misaligned(BootInfoField::MemoryRegions)
+        );
+
+        let mut raw_boot_info = valid;
+        raw_boot_info.memory_regions = RawSlice {
+            data: MEMORY_REGIONS.as_ptr(),
+            len: u64::MAX / 8,
+        };
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::TooLarge(BootInfoField::MemoryRegions)
+        );
+    }
+
+    #[test]
+    fn empty_phys_addr_range() {
+        let mut raw_boot_info = raw_boot_info();
+        raw_boot_info.hardware_info.phys_addr_end = raw_boot_info.hardware_info.phys_addr_start;
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(err.kind(), &InvalidBootInfoErrorKind::EmptyPhysAddrRange);
+    }
+
+    #[test]
+    fn boot_time_out_of_range() {
+        let mut raw_boot_info = raw_boot_info();
+        raw_boot_info.platform_info = RawPlatformInfo::Uhyve {
+            has_pci: false,
+            num_cpus: NonZeroU64::new(1).unwrap(),
+            cpu_freq: None,
+            boot_time: i128::MAX.to_ne_bytes().into(),
+        };
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(err.kind(), &InvalidBootInfoErrorKind::BootTimeOutOfRange);
+    }
+
+    #[test]
+    fn command_line_not_utf8() {
+        static COMMAND_LINE: [u8; 2] = [b'-', 0xff];
+        let mut raw_boot_info = raw_boot_info();
+        let RawPlatformInfo::LinuxBootParams {
+            command_line_data,
+            command_line_len,
+            ..
+        } = &mut raw_boot_info.platform_info
+        else {
+            unreachable!()
+        };
+        *command_line_data = COMMAND_LINE.as_ptr();
+        *command_line_len = COMMAND_LINE.len() as u64;
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::NotUtf8(BootInfoField::CommandLine)
+        );
+    }
+
+    #[test]
+    fn null_command_line() {
+        let mut raw_boot_info = raw_boot_info();
+        let RawPlatformInfo::LinuxBootParams {
+            command_line_data, ..
+        } = &mut raw_boot_info.platform_info
+        else {
+            unreachable!()
+        };
+        *command_line_data = core::ptr::null();
+        let err = BootInfo::try_from(raw_boot_info).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &InvalidBootInfoErrorKind::NullPointer(BootInfoField::CommandLine)
+        );
     }
 }
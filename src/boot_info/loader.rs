@@ -1,10 +1,111 @@
+use alloc::string::String;
+
 use super::{
-    BootInfo, HardwareInfo, LoadInfo, PlatformInfo, RawBootInfo, RawHardwareInfo, RawLoadInfo,
-    RawPlatformInfo, TlsInfo,
+    Arguments, BootInfo, HardwareInfo, IncompatibleBootInfoError, LegacyI128, LoadInfo,
+    PlatformInfo, RawArguments, RawBootInfo, RawBootInfoHeader, RawBootInfoV3, RawBootInfoV4,
+    RawHardwareInfo, RawLoadInfo, RawPlatformInfo, RawPlatformInfoV3, RawSlice, StrList, TlsInfo,
 };
+use crate::config::Input;
+
+impl StrList {
+    /// Encodes strings into a buffer for [`StrList::new`].
+    ///
+    /// Returns [`None`] if a string contains NUL.
+    ///
+    /// See [`StrList::new`] for where to place the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hermit_entry::boot_info::StrList;
+    ///
+    /// let buf = StrList::encode(["-freq", "2000"]).unwrap();
+    /// let list = StrList::new(buf.leak()).unwrap();
+    /// assert!(list.iter().eq(["-freq", "2000"]));
+    /// ```
+    pub fn encode<I>(strs: I) -> Option<String>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut buf = String::new();
+        for s in strs {
+            let s = s.as_ref();
+            if s.contains('\0') {
+                return None;
+            }
+            buf.push_str(s);
+            buf.push('\0');
+        }
+        Some(buf)
+    }
+}
+
+impl Arguments {
+    /// Encodes buffers for [`StrList::new`] from [`Input`] of the image configuration.
+    ///
+    /// Returns the buffers for [`Self::kernel_args`], [`Self::app_args`], and [`Self::env_vars`],
+    /// or [`None`] if a string contains NUL.
+    ///
+    /// See [`StrList::new`] for where to place the buffers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hermit_entry::boot_info::{Arguments, StrList};
+    /// use hermit_entry::config::Input;
+    ///
+    /// let input = Input {
+    ///     kernel_args: vec!["-freq".into(), "2000".into()],
+    ///     app_args: vec![],
+    ///     env_vars: vec!["RUST_LOG=info".into()],
+    /// };
+    /// let [kernel_args, app_args, env_vars] = Arguments::encode_input(&input)
+    ///     .unwrap()
+    ///     .map(|buf| StrList::new(buf.leak()).unwrap());
+    /// let arguments = Arguments {
+    ///     kernel_args,
+    ///     app_args,
+    ///     env_vars,
+    /// };
+    /// assert!(arguments.env_vars.iter().eq(["RUST_LOG=info"]));
+    /// ```
+    pub fn encode_input(input: &Input<'_>) -> Option<[String; 3]> {
+        Some([
+            StrList::encode(&input.kernel_args)?,
+            StrList::encode(&input.app_args)?,
+            StrList::encode(&input.env_vars)?,
+        ])
+    }
+}
+
+impl From<StrList> for RawSlice<u8> {
+    fn from(str_list: StrList) -> Self {
+        str_list.as_buf().as_bytes().into()
+    }
+}
+
+impl From<Arguments> for RawArguments {
+    fn from(arguments: Arguments) -> Self {
+        Self {
+            kernel_args: arguments.kernel_args.into(),
+            app_args: arguments.app_args.into(),
+            env_vars: arguments.env_vars.into(),
+        }
+    }
+}
+
+impl<T> From<&'static [T]> for RawSlice<T> {
+    fn from(slice: &'static [T]) -> Self {
+        Self {
+            data: slice.as_ptr(),
+            len: slice.len() as u64,
+        }
+    }
+}
 
-impl From<HardwareInfo> for RawHardwareInfo {
-    fn from(hardware_info: HardwareInfo) -> Self {
+impl From<&HardwareInfo> for RawHardwareInfo {
+    fn from(hardware_info: &HardwareInfo) -> Self {
         Self {
             phys_addr_start: hardware_info.phys_addr_range.start,
             phys_addr_end: hardware_info.phys_addr_range.end,
@@ -73,10 +174,188 @@ impl From<PlatformInfo> for RawPlatformInfo {
 
 impl From<BootInfo> for RawBootInfo {
     fn from(boot_info: BootInfo) -> Self {
+        let initrds = boot_info.load_info.initrds.into();
+        let irelative_relocations = boot_info.load_info.irelative_relocations.into();
         RawBootInfo {
-            hardware_info: boot_info.hardware_info.into(),
+            header: RawBootInfoHeader::new(),
+            hardware_info: (&boot_info.hardware_info).into(),
             load_info: boot_info.load_info.into(),
             platform_info: boot_info.platform_info.into(),
+            memory_regions: boot_info.hardware_info.memory_regions.into(),
+            initrds,
+            arguments: boot_info.arguments.into(),
+            irelative_relocations,
+        }
+    }
+}
+
+impl BootInfo {
+    /// Checks that no fields are used that were added after `entry_version`.
+    ///
+    /// These fields cannot be represented in the layouts for older hermit entry versions.
+    fn check_entry_version(&self, entry_version: u8) -> Result<(), IncompatibleBootInfoError> {
+        let compatible = self.hardware_info.memory_regions.is_empty()
+            && self.load_info.initrds.is_empty()
+            && self.load_info.irelative_relocations.is_empty()
+            && self.arguments.kernel_args.as_buf().is_empty()
+            && self.arguments.app_args.as_buf().is_empty()
+            && self.arguments.env_vars.as_buf().is_empty();
+        if !compatible {
+            return Err(IncompatibleBootInfoError { entry_version });
         }
+        Ok(())
+    }
+}
+
+impl RawBootInfoV4 {
+    /// Returns a pointer to this boot information for passing it to [`Entry`](crate::Entry).
+    ///
+    /// Kernels with hermit entry version 4 read the pointer using this layout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(target_arch = "riscv64"))]
+    /// # {
+    /// use hermit_entry::Entry;
+    /// use hermit_entry::boot_info::RawBootInfoV4;
+    ///
+    /// /// Enters a kernel with hermit entry version 4.
+    /// unsafe fn enter(entry: Entry, raw_boot_info: &'static RawBootInfoV4, cpu_id: u32) -> ! {
+    ///     unsafe { entry(raw_boot_info.as_ptr(), cpu_id) }
+    /// }
+    /// # }
+    /// ```
+    pub fn as_ptr(&self) -> *const RawBootInfo {
+        (self as *const Self).cast()
+    }
+}
+
+impl TryFrom<BootInfo> for RawBootInfoV4 {
+    type Error = IncompatibleBootInfoError;
+
+    fn try_from(boot_info: BootInfo) -> Result<Self, Self::Error> {
+        boot_info.check_entry_version(4)?;
+        let raw_boot_info = RawBootInfo::from(boot_info);
+        Ok(Self {
+            hardware_info: raw_boot_info.hardware_info,
+            load_info: raw_boot_info.load_info,
+            platform_info: raw_boot_info.platform_info,
+        })
+    }
+}
+
+impl TryFrom<RawPlatformInfo> for RawPlatformInfoV3 {
+    type Error = IncompatibleBootInfoError;
+
+    fn try_from(raw_platform_info: RawPlatformInfo) -> Result<Self, Self::Error> {
+        let platform_info = match raw_platform_info {
+            #[cfg(target_arch = "x86_64")]
+            RawPlatformInfo::Multiboot {
+                command_line_data,
+                command_line_len,
+                multiboot_info_addr,
+            } => Self::Multiboot {
+                command_line_data,
+                command_line_len,
+                multiboot_info_addr,
+            },
+            #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+            RawPlatformInfo::LinuxBoot => Self::LinuxBoot,
+            RawPlatformInfo::Uhyve {
+                has_pci,
+                num_cpus,
+                cpu_freq,
+                boot_time,
+            } => Self::Uhyve {
+                has_pci,
+                num_cpus,
+                cpu_freq,
+                boot_time: LegacyI128(boot_time.0),
+            },
+            RawPlatformInfo::LinuxBootParams {
+                command_line_data,
+                command_line_len,
+                boot_params_addr,
+            } => Self::LinuxBootParams {
+                command_line_data,
+                command_line_len,
+                boot_params_addr,
+            },
+            RawPlatformInfo::Fdt => return Err(IncompatibleBootInfoError { entry_version: 3 }),
+        };
+        Ok(platform_info)
+    }
+}
+
+impl RawBootInfoV3 {
+    /// Returns a pointer to this boot information for passing it to [`Entry`](crate::Entry).
+    ///
+    /// Kernels with hermit entry version 3 read the pointer using this layout.
+    /// See [`RawBootInfoV4::as_ptr`].
+    pub fn as_ptr(&self) -> *const RawBootInfo {
+        (self as *const Self).cast()
+    }
+}
+
+impl TryFrom<BootInfo> for RawBootInfoV3 {
+    type Error = IncompatibleBootInfoError;
+
+    fn try_from(boot_info: BootInfo) -> Result<Self, Self::Error> {
+        boot_info.check_entry_version(3)?;
+        let raw_boot_info = RawBootInfo::from(boot_info);
+        Ok(Self {
+            hardware_info: raw_boot_info.hardware_info,
+            load_info: raw_boot_info.load_info,
+            platform_info: raw_boot_info.platform_info.try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroU64;
+
+    use super::*;
+    use crate::boot_info::{MemoryRegion, MemoryRegionKind};
+
+    fn boot_info() -> BootInfo {
+        BootInfo {
+            hardware_info: HardwareInfo {
+                phys_addr_range: 0..0x1000_0000,
+                serial_port_base: None,
+                device_tree: None,
+                memory_regions: &[],
+            },
+            load_info: LoadInfo {
+                kernel_image_addr_range: 0x20_0000..0x40_0000,
+                tls_info: None,
+                initrds: &[],
+                irelative_relocations: &[],
+            },
+            platform_info: PlatformInfo::LinuxBootParams {
+                command_line: None,
+                boot_params_addr: NonZeroU64::new(0x7000).unwrap(),
+            },
+            arguments: Arguments::default(),
+        }
+    }
+
+    #[test]
+    fn incompatible() {
+        assert!(RawBootInfoV3::try_from(boot_info()).is_ok());
+        assert!(RawBootInfoV4::try_from(boot_info()).is_ok());
+
+        let mut with_regions = boot_info();
+        static MEMORY_REGIONS: [MemoryRegion; 1] =
+            [MemoryRegion::new(0..0x1000_0000, MemoryRegionKind::Usable)];
+        with_regions.hardware_info.memory_regions = &MEMORY_REGIONS;
+        let err = RawBootInfoV4::try_from(with_regions).unwrap_err();
+        assert_eq!(err.entry_version(), 4);
+
+        let mut with_args = boot_info();
+        with_args.arguments.app_args = StrList::new("app\0").unwrap();
+        let err = RawBootInfoV3::try_from(with_args).unwrap_err();
+        assert_eq!(err.entry_version(), 3);
     }
 }
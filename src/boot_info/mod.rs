@@ -2,7 +2,8 @@
 //!
 //! Loaders assemble [`BootInfo`] and convert it into a [`RawBootInfo`] to pass it to the kernel.
 //!
-//! The kernel copies [`BootInfo`] from [`RawBootInfo`] to work with the values.
+//! The kernel reads [`RawBootInfo`] using `RawBootInfo::read` and copies [`BootInfo`] from it to work with the values.
+//! This is done using [`TryFrom`], which validates the values from the loader.
 
 #[cfg(feature = "loader")]
 mod loader;
@@ -46,8 +47,77 @@ pub struct BootInfo {
 
     /// Platform information.
     pub platform_info: PlatformInfo,
+
+    /// Arguments and environment.
+    ///
+    /// Unlike command lines in [`PlatformInfo`], these are available on all platforms.
+    pub arguments: Arguments,
+}
+
+/// Arguments and environment variables for the kernel and the application.
+///
+/// This corresponds to `config::Input` from the image configuration.
+/// Loaders can encode it using `Arguments::encode_input`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Arguments {
+    /// Arguments to be passed to the kernel.
+    pub kernel_args: StrList,
+
+    /// Arguments to be passed to the application.
+    pub app_args: StrList,
+
+    /// Environment variables.
+    pub env_vars: StrList,
+}
+
+/// A list of strings, stored as NUL-terminated strings in one buffer.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrList(&'static str);
+
+impl StrList {
+    /// Creates a list from a buffer of NUL-terminated strings.
+    ///
+    /// Returns [`None`] if `buf` is not empty and does not end with NUL.
+    /// See `StrList::encode` for creating such a buffer.
+    ///
+    /// The kernel reads the buffer through [`RawBootInfo`] after the loader has handed over control.
+    /// Loaders have to place it in memory that the kernel can access and that is not reused while the kernel runs.
+    /// Loaders sharing the kernel's address space can use `String::leak`.
+    pub fn new(buf: &'static str) -> Option<Self> {
+        (buf.is_empty() || buf.ends_with('\0')).then_some(Self(buf))
+    }
+
+    /// Returns the underlying buffer of NUL-terminated strings.
+    pub fn as_buf(&self) -> &'static str {
+        self.0
+    }
+
+    /// Returns an iterator over the strings.
+    pub fn iter(&self) -> core::str::SplitTerminator<'static, char> {
+        self.0.split_terminator('\0')
+    }
 }
 
+impl IntoIterator for StrList {
+    type Item = &'static str;
+    type IntoIter = core::str::SplitTerminator<'static, char>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for StrList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BootInfo>();
+};
+
 /// Hardware information.
 #[derive(Debug)]
 pub struct HardwareInfo {
@@ -59,6 +129,85 @@ pub struct HardwareInfo {
 
     /// Address of the device tree
     pub device_tree: Option<DeviceTreeAddress>,
+
+    /// The physical memory map.
+    ///
+    /// This describes physical memory in more detail than [`Self::phys_addr_range`].
+    /// Regions should be sorted by address and must not overlap.
+    /// This is empty if the loader does not provide a memory map.
+    pub memory_regions: &'static [MemoryRegion],
+}
+
+/// A region of physical memory.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    start: u64,
+    end: u64,
+    kind: u32,
+}
+
+impl MemoryRegion {
+    /// Creates a new memory region.
+    pub const fn new(range: Range<u64>, kind: MemoryRegionKind) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+            kind: kind as u32,
+        }
+    }
+
+    /// Returns the physical address range of this region.
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// Returns the kind of this region.
+    ///
+    /// Kinds unknown to this version of the crate are reported as [`MemoryRegionKind::Reserved`].
+    pub fn kind(&self) -> MemoryRegionKind {
+        match self.kind {
+            1 => MemoryRegionKind::Usable,
+            3 => MemoryRegionKind::Acpi,
+            4 => MemoryRegionKind::KernelImage,
+            5 => MemoryRegionKind::BootInfo,
+            6 => MemoryRegionKind::Initrd,
+            _ => MemoryRegionKind::Reserved,
+        }
+    }
+}
+
+impl fmt::Debug for MemoryRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryRegion")
+            .field("range", &format_args!("{:#x}..{:#x}", self.start, self.end))
+            .field("kind", &self.kind())
+            .finish()
+    }
+}
+
+/// The kind of a [`MemoryRegion`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[repr(u32)]
+pub enum MemoryRegionKind {
+    /// Memory that is free to use.
+    Usable = 1,
+
+    /// Memory that must not be used, such as firmware memory or MMIO windows.
+    Reserved = 2,
+
+    /// ACPI tables that can be reclaimed after they have been parsed.
+    Acpi = 3,
+
+    /// The loaded kernel image.
+    KernelImage = 4,
+
+    /// Boot information from the loader, including [`RawBootInfo`] and the data it points to.
+    BootInfo = 5,
+
+    /// An initial RAM disk.
+    Initrd = 6,
 }
 
 /// Load information.
@@ -69,6 +218,109 @@ pub struct LoadInfo {
 
     /// Kernel image TLS information.
     pub tls_info: Option<TlsInfo>,
+
+    /// Initial RAM disks and other modules loaded for the kernel.
+    pub initrds: &'static [Initrd],
+
+    /// `R_*_IRELATIVE` relocations that the kernel has to resolve before using the relocated values.
+    ///
+    /// See `IRelativeRelocation::apply`.
+    pub irelative_relocations: &'static [IRelativeRelocation],
+}
+
+/// An initial RAM disk or another module loaded for the kernel.
+///
+/// This can be used for passing root filesystems to the kernel.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Initrd {
+    start: u64,
+    end: u64,
+    name: RawSlice<u8>,
+}
+
+impl Initrd {
+    /// Creates a new initrd descriptor.
+    pub const fn new(addr_range: Range<u64>, name: Option<&'static str>) -> Self {
+        let name = match name {
+            Some(name) => RawSlice {
+                data: name.as_ptr(),
+                len: name.len() as u64,
+            },
+            None => RawSlice {
+                data: core::ptr::null(),
+                len: 0,
+            },
+        };
+        Self {
+            start: addr_range.start,
+            end: addr_range.end,
+            name,
+        }
+    }
+
+    /// Returns the physical address range of this initrd.
+    pub fn addr_range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// Returns the length of this initrd in bytes.
+    ///
+    /// Returns `0` if the end of the address range is below its start.
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns `true` if this initrd is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the name of this initrd, such as its file name, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        if self.name.data.is_null() {
+            return None;
+        }
+
+        // SAFETY: `Self::new` takes a `&'static str`.
+        let name = unsafe { core::slice::from_raw_parts(self.name.data, self.name.len as usize) };
+        core::str::from_utf8(name).ok()
+    }
+}
+
+impl fmt::Debug for Initrd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Initrd")
+            .field(
+                "addr_range",
+                &format_args!("{:#x}..{:#x}", self.start, self.end),
+            )
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// An `R_*_IRELATIVE` relocation.
+///
+/// The loader cannot execute kernel code, so the relocation target is initialized with the resolver address.
+/// The kernel has to call the resolver and store the returned value at [`Self::addr`] before using it.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IRelativeRelocation {
+    /// The virtual address of the relocation target.
+    pub addr: u64,
+
+    /// The virtual address of the resolver function.
+    pub resolver: u64,
+}
+
+impl fmt::Debug for IRelativeRelocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IRelativeRelocation")
+            .field("addr", &format_args!("{:#x}", self.addr))
+            .field("resolver", &format_args!("{:#x}", self.resolver))
+            .finish()
+    }
 }
 
 /// Platform information.
@@ -148,14 +400,98 @@ impl fmt::Debug for TlsInfo {
 /// The raw boot information struct.
 ///
 /// This is kept separate from [`BootInfo`] to make non-breaking API evolution possible.
+///
+/// The struct starts with a header that specifies the hermit entry version and the size of the struct.
+/// The header's checksum only covers the header itself.
+/// New fields are appended without bumping the hermit entry version.
+/// Kernels read fields that are missing in the loader's layout as empty (see `RawBootInfo::read`).
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
 pub struct RawBootInfo {
+    header: RawBootInfoHeader,
     hardware_info: RawHardwareInfo,
     load_info: RawLoadInfo,
     platform_info: RawPlatformInfo,
+
+    /// Added in hermit entry version 5.
+    memory_regions: RawSlice<MemoryRegion>,
+
+    /// Added in hermit entry version 5.
+    initrds: RawSlice<Initrd>,
+
+    /// Added in hermit entry version 5.
+    arguments: RawArguments,
+
+    /// Added in hermit entry version 5.
+    irelative_relocations: RawSlice<IRelativeRelocation>,
+}
+
+/// The header of [`RawBootInfo`].
+///
+/// Like the Multiboot header, all fields sum up to zero.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct RawBootInfoHeader {
+    magic: u32,
+    entry_version: u32,
+    size: u32,
+
+    /// The checksum of the header fields.
+    ///
+    /// This only protects the header, not the rest of [`RawBootInfo`].
+    /// The rest contains padding bytes, which cannot be checksummed.
+    header_checksum: u32,
+}
+
+impl RawBootInfoHeader {
+    /// The magic value of [`RawBootInfo`].
+    #[cfg_attr(not(any(feature = "loader", feature = "kernel")), expect(dead_code))]
+    const MAGIC: u32 = u32::from_le_bytes(*b"HRMT");
+
+    /// The smallest size of [`RawBootInfo`] for the current hermit entry version.
+    ///
+    /// Fields after this offset have been appended later.
+    #[cfg(feature = "kernel")]
+    const MIN_SIZE: usize = core::mem::offset_of!(RawBootInfo, memory_regions);
+
+    #[cfg(feature = "loader")]
+    const fn new() -> Self {
+        let magic = Self::MAGIC;
+        let entry_version = crate::HERMIT_ENTRY_VERSION as u32;
+        let size = core::mem::size_of::<RawBootInfo>() as u32;
+        let header_checksum =
+            0u32.wrapping_sub(magic.wrapping_add(entry_version).wrapping_add(size));
+        Self {
+            magic,
+            entry_version,
+            size,
+            header_checksum,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct RawArguments {
+    kernel_args: RawSlice<u8>,
+    app_args: RawSlice<u8>,
+    env_vars: RawSlice<u8>,
 }
 
+/// A `&'static [T]` with a stable layout.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct RawSlice<T> {
+    data: *const T,
+    len: u64,
+}
+
+// SAFETY: `RawSlice` only refers to immutable `'static` data, just like `&'static [T]`.
+unsafe impl<T: Sync> Send for RawSlice<T> {}
+
+// SAFETY: `RawSlice` only refers to immutable `'static` data, just like `&'static [T]`.
+unsafe impl<T: Sync> Sync for RawSlice<T> {}
+
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
 struct RawHardwareInfo {
@@ -184,7 +520,7 @@ impl<T> From<T> for Align8<T> {
     }
 }
 
-#[cfg_attr(not(all(feature = "loader", feature = "kernel")), expect(dead_code))]
+#[cfg_attr(not(feature = "loader"), expect(dead_code))]
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
 enum RawPlatformInfo {
@@ -209,3 +545,281 @@ enum RawPlatformInfo {
     },
     Fdt,
 }
+
+/// The raw boot information struct for kernels with hermit entry version 3.
+///
+/// Entry version 3 predates [`PlatformInfo::Fdt`] and stores the boot time as `i128`,
+/// which was 8-byte aligned on x86-64 at that time.
+/// Converting [`BootInfo`] fails if it uses [`PlatformInfo::Fdt`] or any field unknown to entry version 4.
+/// See [`KernelObject::entry_version`](crate::elf::KernelObject::entry_version)
+/// and [`RawBootInfoV4::as_ptr`] for passing it to the kernel.
+#[cfg(feature = "loader")]
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RawBootInfoV3 {
+    hardware_info: RawHardwareInfo,
+    load_info: RawLoadInfo,
+    platform_info: RawPlatformInfoV3,
+}
+
+/// The raw boot information struct for kernels with hermit entry version 4.
+///
+/// Entry version 4 predates the [`RawBootInfo`] header and all fields after [`PlatformInfo`].
+/// Converting [`BootInfo`] fails if [`HardwareInfo::memory_regions`], [`LoadInfo::initrds`],
+/// [`LoadInfo::irelative_relocations`], or [`BootInfo::arguments`] are not empty.
+/// See [`KernelObject::entry_version`](crate::elf::KernelObject::entry_version).
+#[cfg(feature = "loader")]
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RawBootInfoV4 {
+    hardware_info: RawHardwareInfo,
+    load_info: RawLoadInfo,
+    platform_info: RawPlatformInfo,
+}
+
+// Older kernels depend on these layouts.
+#[cfg(feature = "loader")]
+const _: () = {
+    use core::mem::{align_of, offset_of, size_of};
+
+    assert!(offset_of!(RawBootInfoV4, load_info) == 32);
+    assert!(offset_of!(RawBootInfoV4, platform_info) == 80);
+    assert!(size_of::<RawBootInfoV4>() == 128);
+    assert!(align_of::<RawBootInfoV4>() == 8);
+
+    assert!(offset_of!(RawBootInfoV3, load_info) == 32);
+    assert!(offset_of!(RawBootInfoV3, platform_info) == 80);
+};
+
+#[cfg(all(feature = "loader", target_arch = "x86_64"))]
+const _: () = {
+    assert!(core::mem::size_of::<RawBootInfoV3>() == 128);
+    assert!(core::mem::align_of::<RawBootInfoV3>() == 8);
+};
+
+// `LegacyI128` is 16-byte aligned, unlike `Align8`.
+#[cfg(all(feature = "loader", not(target_arch = "x86_64")))]
+const _: () = {
+    assert!(core::mem::size_of::<RawBootInfoV3>() == 144);
+    assert!(core::mem::align_of::<RawBootInfoV3>() == 16);
+};
+
+/// The layout of `i128` before Rust 1.77.
+#[cfg(feature = "loader")]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(target_arch = "x86_64", repr(C, align(8)))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C, align(16)))]
+struct LegacyI128([u8; 16]);
+
+/// This is only read by kernels with entry version 3.
+#[cfg(feature = "loader")]
+#[expect(dead_code)]
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+enum RawPlatformInfoV3 {
+    #[cfg(target_arch = "x86_64")]
+    Multiboot {
+        command_line_data: *const u8,
+        command_line_len: u64,
+        multiboot_info_addr: core::num::NonZeroU64,
+    },
+    #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+    LinuxBoot,
+    Uhyve {
+        has_pci: bool,
+        num_cpus: NonZeroU64,
+        cpu_freq: Option<NonZeroU32>,
+        boot_time: LegacyI128,
+    },
+    LinuxBootParams {
+        command_line_data: *const u8,
+        command_line_len: u64,
+        boot_params_addr: core::num::NonZeroU64,
+    },
+}
+
+/// An error returned when boot information cannot be represented for an older hermit entry version.
+#[cfg(feature = "loader")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleBootInfoError {
+    entry_version: u8,
+}
+
+#[cfg(feature = "loader")]
+impl IncompatibleBootInfoError {
+    /// Returns the hermit entry version that cannot represent the boot information.
+    pub fn entry_version(&self) -> u8 {
+        self.entry_version
+    }
+}
+
+#[cfg(feature = "loader")]
+impl fmt::Display for IncompatibleBootInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entry_version = self.entry_version;
+        write!(
+            f,
+            "boot information is not supported by hermit entry version {entry_version}"
+        )
+    }
+}
+
+#[cfg(feature = "loader")]
+impl core::error::Error for IncompatibleBootInfoError {}
+
+/// An error returned when [`RawBootInfo`] from the loader is invalid.
+///
+/// See [`BootInfo::try_from`].
+#[cfg(feature = "kernel")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidBootInfoError(InvalidBootInfoErrorKind);
+
+#[cfg(feature = "kernel")]
+impl InvalidBootInfoError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &InvalidBootInfoErrorKind {
+        &self.0
+    }
+}
+
+#[cfg(feature = "kernel")]
+impl From<InvalidBootInfoErrorKind> for InvalidBootInfoError {
+    #[inline]
+    fn from(kind: InvalidBootInfoErrorKind) -> Self {
+        Self(kind)
+    }
+}
+
+#[cfg(feature = "kernel")]
+impl fmt::Display for InvalidBootInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = &self.0;
+        write!(f, "invalid boot information: {kind}")
+    }
+}
+
+#[cfg(feature = "kernel")]
+impl core::error::Error for InvalidBootInfoError {}
+
+/// The kind of an [`InvalidBootInfoError`].
+#[cfg(feature = "kernel")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidBootInfoErrorKind {
+    /// The boot information does not start with the magic value.
+    InvalidMagic {
+        /// The value found instead of the magic value.
+        found: u32,
+    },
+
+    /// The header checksum is wrong.
+    ChecksumMismatch,
+
+    /// The boot information is for a different hermit entry version.
+    EntryVersionMismatch {
+        /// The hermit entry version of the boot information.
+        found: u32,
+    },
+
+    /// The boot information is smaller than required for this hermit entry version.
+    TooSmall {
+        /// The size of the boot information.
+        size: u32,
+    },
+
+    /// The physical address range is empty.
+    EmptyPhysAddrRange,
+
+    /// A pointer is null although its length is not zero.
+    NullPointer(BootInfoField),
+
+    /// A pointer is not aligned for its type.
+    Misaligned(BootInfoField),
+
+    /// A slice is larger than `isize::MAX` bytes.
+    TooLarge(BootInfoField),
+
+    /// An address range ends before it starts.
+    InvalidRange(BootInfoField),
+
+    /// A string is not valid UTF-8.
+    NotUtf8(BootInfoField),
+
+    /// A string list does not end with NUL.
+    NotNulTerminated(BootInfoField),
+
+    /// The boot time is out of range.
+    BootTimeOutOfRange,
+}
+
+#[cfg(feature = "kernel")]
+impl fmt::Display for InvalidBootInfoErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic { found } => write!(f, "invalid magic value {found:#x}"),
+            Self::ChecksumMismatch => f.write_str("header checksum mismatch"),
+            Self::EntryVersionMismatch { found } => write!(
+                f,
+                "hermit entry version {found} does not match {}",
+                crate::HERMIT_ENTRY_VERSION
+            ),
+            Self::TooSmall { size } => write!(f, "size {size} is too small"),
+            Self::EmptyPhysAddrRange => f.write_str("physical address range is empty"),
+            Self::NullPointer(field) => write!(f, "{field} is null but not empty"),
+            Self::Misaligned(field) => write!(f, "{field} is misaligned"),
+            Self::TooLarge(field) => write!(f, "{field} is too large"),
+            Self::InvalidRange(field) => {
+                write!(f, "{field} has an address range that ends before it starts")
+            }
+            Self::NotUtf8(field) => write!(f, "{field} is not valid UTF-8"),
+            Self::NotNulTerminated(field) => write!(f, "{field} is not NUL-terminated"),
+            Self::BootTimeOutOfRange => f.write_str("boot time is out of range"),
+        }
+    }
+}
+
+/// A field of [`RawBootInfo`].
+#[cfg(feature = "kernel")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BootInfoField {
+    /// The command line of the platform.
+    CommandLine,
+
+    /// [`HardwareInfo::memory_regions`].
+    MemoryRegions,
+
+    /// [`LoadInfo::initrds`].
+    Initrds,
+
+    /// The name of an [`Initrd`].
+    InitrdName,
+
+    /// [`Arguments::kernel_args`].
+    KernelArgs,
+
+    /// [`Arguments::app_args`].
+    AppArgs,
+
+    /// [`Arguments::env_vars`].
+    EnvVars,
+
+    /// [`LoadInfo::irelative_relocations`].
+    IRelativeRelocations,
+}
+
+#[cfg(feature = "kernel")]
+impl fmt::Display for BootInfoField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CommandLine => f.write_str("command line"),
+            Self::MemoryRegions => f.write_str("memory regions"),
+            Self::Initrds => f.write_str("initrds"),
+            Self::InitrdName => f.write_str("initrd name"),
+            Self::KernelArgs => f.write_str("kernel arguments"),
+            Self::AppArgs => f.write_str("application arguments"),
+            Self::EnvVars => f.write_str("environment variables"),
+            Self::IRelativeRelocations => f.write_str("IRELATIVE relocations"),
+        }
+    }
+}
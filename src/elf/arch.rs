@@ -0,0 +1,191 @@
+use core::fmt;
+
+use goblin::elf::{header, reloc};
+
+/// A CPU architecture supported by Hermit.
+///
+/// The architecture determines which ELF machine is accepted and how relocations are applied.
+/// It is independent of the architecture the loader itself runs on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Arch {
+    /// x86-64.
+    X86_64,
+
+    /// AArch64.
+    Aarch64,
+
+    /// 64-bit RISC-V.
+    Riscv64,
+}
+
+impl Arch {
+    /// The architecture this crate is compiled for.
+    #[cfg(target_arch = "x86_64")]
+    pub const HOST: Self = Self::X86_64;
+
+    /// The architecture this crate is compiled for.
+    #[cfg(target_arch = "aarch64")]
+    pub const HOST: Self = Self::Aarch64;
+
+    /// The architecture this crate is compiled for.
+    #[cfg(target_arch = "riscv64")]
+    pub const HOST: Self = Self::Riscv64;
+
+    /// Returns the architecture for an ELF `e_machine` value if supported.
+    pub const fn from_e_machine(e_machine: u16) -> Option<Self> {
+        match e_machine {
+            header::EM_X86_64 => Some(Self::X86_64),
+            header::EM_AARCH64 => Some(Self::Aarch64),
+            header::EM_RISCV => Some(Self::Riscv64),
+            _ => None,
+        }
+    }
+
+    /// Returns the ELF `e_machine` value of this architecture.
+    pub const fn e_machine(self) -> u16 {
+        match self {
+            Self::X86_64 => header::EM_X86_64,
+            Self::Aarch64 => header::EM_AARCH64,
+            Self::Riscv64 => header::EM_RISCV,
+        }
+    }
+
+    /// Returns the ELF data encoding (`EI_DATA`) of this architecture.
+    ///
+    /// Hermit only supports little-endian targets.
+    pub const fn data_encoding(self) -> u8 {
+        match self {
+            Self::X86_64 | Self::Aarch64 | Self::Riscv64 => header::ELFDATA2LSB,
+        }
+    }
+
+    /// Classifies a relocation type of this architecture.
+    pub(crate) fn relocation_kind(self, r_type: u32) -> Option<RelocationKind> {
+        let kind = match self {
+            // See https://refspecs.linuxbase.org/elf/x86_64-abi-0.98.pdf
+            Self::X86_64 => match r_type {
+                reloc::R_X86_64_64 => RelocationKind::Abs64,
+                reloc::R_X86_64_RELATIVE => RelocationKind::Relative,
+                reloc::R_X86_64_GLOB_DAT => RelocationKind::GlobDat,
+                reloc::R_X86_64_JUMP_SLOT => RelocationKind::JumpSlot,
+                reloc::R_X86_64_IRELATIVE => RelocationKind::IRelative,
+                reloc::R_X86_64_DTPMOD64 => RelocationKind::TlsDtpMod,
+                reloc::R_X86_64_DTPOFF64 => RelocationKind::TlsDtpOff,
+                reloc::R_X86_64_TPOFF64 => RelocationKind::TlsTpOff,
+                _ => return None,
+            },
+            // See https://github.com/ARM-software/abi-aa/blob/2023Q3/aaelf64/aaelf64.rst#relocation
+            Self::Aarch64 => match r_type {
+                reloc::R_AARCH64_ABS64 => RelocationKind::Abs64,
+                reloc::R_AARCH64_RELATIVE => RelocationKind::Relative,
+                reloc::R_AARCH64_GLOB_DAT => RelocationKind::GlobDat,
+                reloc::R_AARCH64_JUMP_SLOT => RelocationKind::JumpSlot,
+                reloc::R_AARCH64_IRELATIVE => RelocationKind::IRelative,
+                reloc::R_AARCH64_TLS_DTPMOD => RelocationKind::TlsDtpMod,
+                reloc::R_AARCH64_TLS_DTPREL => RelocationKind::TlsDtpOff,
+                reloc::R_AARCH64_TLS_TPREL => RelocationKind::TlsTpOff,
+                _ => return None,
+            },
+            // See https://github.com/riscv-non-isa/riscv-elf-psabi-doc/blob/v1.0/riscv-elf.adoc#relocations
+            Self::Riscv64 => match r_type {
+                reloc::R_RISCV_64 => RelocationKind::Abs64,
+                reloc::R_RISCV_RELATIVE => RelocationKind::Relative,
+                reloc::R_RISCV_JUMP_SLOT => RelocationKind::JumpSlot,
+                reloc::R_RISCV_IRELATIVE => RelocationKind::IRelative,
+                reloc::R_RISCV_TLS_DTPMOD64 => RelocationKind::TlsDtpMod,
+                reloc::R_RISCV_TLS_DTPREL64 => RelocationKind::TlsDtpOff,
+                reloc::R_RISCV_TLS_TPREL64 => RelocationKind::TlsTpOff,
+                _ => return None,
+            },
+        };
+        Some(kind)
+    }
+
+    /// Returns the relocation type for `B + A`.
+    pub(crate) fn relative_r_type(self) -> u32 {
+        match self {
+            Self::X86_64 => reloc::R_X86_64_RELATIVE,
+            Self::Aarch64 => reloc::R_AARCH64_RELATIVE,
+            Self::Riscv64 => reloc::R_RISCV_RELATIVE,
+        }
+    }
+
+    /// Returns the offset of the static TLS block from the thread pointer.
+    ///
+    /// x86-64 uses TLS variant II, where the TLS block is located right below the thread pointer.
+    /// AArch64 uses TLS variant I with a 16-byte TCB in front of the TLS block.
+    /// RISC-V uses TLS variant I without a TCB.
+    ///
+    /// Returns [`None`] if the offset does not fit into `i64`.
+    pub(crate) fn tls_block_offset(self, memsz: u64, align: u64) -> Option<i64> {
+        let align = align.max(1);
+        match self {
+            Self::X86_64 => i64::try_from(memsz.checked_next_multiple_of(align)?)
+                .ok()?
+                .checked_neg(),
+            Self::Aarch64 => i64::try_from(16u64.checked_next_multiple_of(align)?).ok(),
+            Self::Riscv64 => Some(0),
+        }
+    }
+
+    /// Returns the bias of offsets into the dynamic thread vector.
+    pub(crate) fn dtv_offset(self) -> u64 {
+        match self {
+            Self::X86_64 | Self::Aarch64 => 0,
+            Self::Riscv64 => 0x800,
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::X86_64 => "x86_64",
+            Self::Aarch64 => "aarch64",
+            Self::Riscv64 => "riscv64",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The architecture-independent meaning of a relocation type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RelocationKind {
+    /// `S + A`
+    Abs64,
+
+    /// `B + A`
+    Relative,
+
+    /// `S + A`, used for GOT entries.
+    GlobDat,
+
+    /// `S + A`, used for PLT entries.
+    JumpSlot,
+
+    /// `B + A` is the address of a resolver function returning the actual value.
+    IRelative,
+
+    /// The module ID of the symbol's TLS block.
+    TlsDtpMod,
+
+    /// The offset of the symbol in its module's TLS block.
+    TlsDtpOff,
+
+    /// The offset of the symbol from the thread pointer in the static TLS block.
+    TlsTpOff,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn e_machine_roundtrip() {
+        for arch in [Arch::X86_64, Arch::Aarch64, Arch::Riscv64] {
+            assert_eq!(Arch::from_e_machine(arch.e_machine()), Some(arch));
+        }
+        assert_eq!(Arch::from_e_machine(header::EM_386), None);
+    }
+}
@@ -0,0 +1,81 @@
+//! Determining the number of dynamic symbols from symbol hash tables.
+//!
+//! The dynamic section does not specify the size of the dynamic symbol table.
+//! Without section headers, it has to be derived from `DT_HASH` or `DT_GNU_HASH`.
+
+use core::mem;
+
+fn read_u32(bytes: &[u8], index: usize) -> Option<u32> {
+    let offset = index.checked_mul(mem::size_of::<u32>())?;
+    let bytes = bytes.get(offset..)?.get(..mem::size_of::<u32>())?;
+    Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+/// Returns the number of symbols from a `DT_HASH` table.
+///
+/// The number of chain entries equals the number of symbols.
+pub(crate) fn sym_count(hash: &[u8]) -> Option<usize> {
+    let nchain = read_u32(hash, 1)?;
+    Some(nchain as usize)
+}
+
+/// Returns the number of symbols from a `DT_GNU_HASH` table.
+///
+/// The table only covers symbols from `symoffset` onwards.
+/// The last symbol is the end of the chain of the highest bucket.
+pub(crate) fn gnu_sym_count(gnu_hash: &[u8]) -> Option<usize> {
+    let nbuckets = read_u32(gnu_hash, 0)? as usize;
+    let symoffset = read_u32(gnu_hash, 1)? as usize;
+    let bloom_size = read_u32(gnu_hash, 2)? as usize;
+
+    // Header and 64-bit bloom filter words
+    let buckets_start = 4usize.checked_add(bloom_size.checked_mul(2)?)?;
+    let chains_start = buckets_start.checked_add(nbuckets)?;
+
+    let mut max_sym = 0;
+    for bucket in 0..nbuckets {
+        max_sym = max_sym.max(read_u32(gnu_hash, buckets_start + bucket)? as usize);
+    }
+    if max_sym < symoffset {
+        return Some(symoffset);
+    }
+
+    // The lowest bit of a chain entry marks the end of the chain.
+    let mut sym = max_sym;
+    while read_u32(gnu_hash, chains_start.checked_add(sym - symoffset)?)? & 1 == 0 {
+        sym += 1;
+    }
+    Some(sym + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_ne_bytes()).collect()
+    }
+
+    #[test]
+    fn count_hash() {
+        assert_eq!(sym_count(&bytes(&[1, 5, 0, 0, 0, 0, 0, 0])), Some(5));
+        assert_eq!(sym_count(&bytes(&[1])), None);
+    }
+
+    #[test]
+    fn count_gnu_hash() {
+        // 2 buckets, symoffset 1, 1 bloom word (two `u32`s), shift 0
+        let table = bytes(&[2, 1, 1, 0, 0, 0, 1, 3, 0, 0, 1]);
+        assert_eq!(gnu_sym_count(&table), Some(4));
+
+        // Empty buckets only cover the local symbols.
+        let table = bytes(&[1, 3, 1, 0, 0, 0, 0]);
+        assert_eq!(gnu_sym_count(&table), Some(3));
+
+        // Unterminated chain
+        let table = bytes(&[1, 1, 1, 0, 0, 0, 1, 0]);
+        assert_eq!(gnu_sym_count(&table), None);
+    }
+}
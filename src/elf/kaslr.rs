@@ -0,0 +1,87 @@
+//! Choosing randomized load addresses for relocatable kernels.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Removes `reserved` ranges from `windows`.
+pub(crate) fn free_ranges(windows: &[Range<u64>], reserved: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut free = windows
+        .iter()
+        .filter(|window| !window.is_empty())
+        .cloned()
+        .collect::<Vec<_>>();
+    for reserved in reserved.iter().filter(|reserved| !reserved.is_empty()) {
+        free = free
+            .into_iter()
+            .flat_map(|range| {
+                let below = range.start..range.end.min(reserved.start);
+                let above = range.start.max(reserved.end)..range.end;
+                [below, above]
+            })
+            .filter(|range| !range.is_empty())
+            .collect();
+    }
+    free
+}
+
+/// Returns the first and number of `align`ed start addresses in `range` that fit `size` bytes.
+fn slots(range: &Range<u64>, size: u64, align: u64) -> (u64, u128) {
+    let Some(first) = range.start.checked_next_multiple_of(align) else {
+        return (0, 0);
+    };
+    match range.end.checked_sub(size) {
+        Some(last) if first <= last => (first, u128::from((last - first) / align) + 1),
+        _ => (first, 0),
+    }
+}
+
+/// Chooses an `align`ed start address for `size` bytes within `free`.
+///
+/// For uniformly distributed `random` values, each of the `n` possible start addresses is chosen
+/// with a probability of `1 / n` up to a relative modulo bias of at most `n / 2^64`.
+/// This is negligible for realistic numbers of start addresses but not strictly uniform.
+pub(crate) fn choose(free: &[Range<u64>], size: u64, align: u64, random: u64) -> Option<u64> {
+    let align = align.max(1);
+    let total = free
+        .iter()
+        .map(|range| slots(range, size, align).1)
+        .sum::<u128>();
+    if total == 0 {
+        return None;
+    }
+
+    let mut index = u128::from(random) % total;
+    for range in free {
+        let (first, count) = slots(range, size, align);
+        if index < count {
+            return Some(first + index as u64 * align);
+        }
+        index -= count;
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtract_reserved() {
+        let free = free_ranges(
+            &[0x1000..0x9000, 0xb000..0xc000],
+            &[0x2000..0x3000, 0x8000..0xb800],
+        );
+        assert_eq!(free, [0x1000..0x2000, 0x3000..0x8000, 0xb800..0xc000]);
+    }
+
+    #[test]
+    fn choose_aligned() {
+        let free = [0x1000..0x2000, 0x3800..0x8000];
+        // The first range is too small.
+        let addrs = (0..4)
+            .map(|random| choose(&free, 0x2000, 0x1000, random).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(addrs, [0x4000, 0x5000, 0x6000, 0x4000]);
+        assert_eq!(choose(&free, 0x5000, 0x1000, 0), None);
+    }
+}
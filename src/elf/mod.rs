@@ -0,0 +1,1747 @@
+//! Parsing and loading kernel objects from ELF files.
+
+mod arch;
+mod hash;
+mod kaslr;
+mod relr;
+mod segment;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::mem::{self, MaybeUninit};
+use core::ops::{Range, RangeInclusive};
+use core::{fmt, str};
+
+use goblin::elf::note;
+use goblin::elf::section_header::{self, SHN_UNDEF};
+use goblin::elf::sym::{self, STB_WEAK};
+use goblin::elf64::dynamic::{self, Dyn};
+use goblin::elf64::header::{self, Header};
+use goblin::elf64::program_header::{self, ProgramHeader};
+use goblin::elf64::reloc::{self, Rel, Rela};
+use goblin::elf64::section_header::SectionHeader;
+use goblin::elf64::sym::Sym;
+use log::{info, warn};
+use plain::Plain;
+
+pub use self::arch::Arch;
+use self::arch::RelocationKind;
+use self::relr::{Relr, RelrIter};
+pub use self::segment::{Segment, SegmentFlags};
+pub use crate::boot_info::IRelativeRelocation;
+use crate::boot_info::{LoadInfo, TlsInfo};
+pub use crate::note::Note;
+use crate::note::{NoteError, NoteIterator};
+use crate::{HermitVersion, KernelRequirements, UhyveIfVersion};
+
+/// The Hermit entry versions of kernels that can be loaded.
+pub const SUPPORTED_ENTRY_VERSIONS: RangeInclusive<u8> = 3..=crate::HERMIT_ENTRY_VERSION;
+
+/// A parsed kernel object ready for loading.
+pub struct KernelObject<'a> {
+    /// The raw bytes of the parsed ELF file.
+    elf: &'a [u8],
+
+    /// The ELF file header at the beginning of [`Self::elf`].
+    header: &'a Header,
+
+    /// The architecture the kernel is compiled for.
+    arch: Arch,
+
+    /// The kernel's program headers.
+    ///
+    /// Loadable program segments will be copied for execution.
+    ///
+    /// The thread-local storage segment will be used for creating [`TlsInfo`] for the kernel.
+    phs: &'a [ProgramHeader],
+
+    /// Relocations with an explicit addend.
+    relas: &'a [Rela],
+
+    /// Relocations with an implicit addend.
+    rels: &'a [Rel],
+
+    /// Packed relative relocations.
+    relrs: &'a [Relr],
+
+    /// Symbol table for relocations
+    ///
+    /// This is located through the dynamic section if possible,
+    /// so that kernels without section headers can be relocated.
+    dynsyms: &'a [Sym],
+
+    /// The kernel's Hermit entry version.
+    entry_version: u8,
+
+    /// The kernel's Hermit version if any.
+    hermit_version: Option<HermitVersion>,
+
+    /// The kernel's Uhyve interface version if any.
+    uhyve_interface_version: Option<UhyveIfVersion>,
+
+    /// The kernel's requirements if any.
+    requirements: Option<KernelRequirements>,
+
+    /// The kernel's default command line if any.
+    default_cmdline: Option<&'a str>,
+}
+
+impl<'a> fmt::Debug for KernelObject<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start_addr = self.start_addr();
+        f.debug_struct("KernelObject")
+            .field("hermit_version", &self.hermit_version)
+            .field("entry_version", &self.entry_version)
+            .field("arch", &self.arch)
+            .field("start_address", &start_addr)
+            .field(
+                "entry_point",
+                &format_args!("{:#x}", self.entry_point(start_addr.unwrap_or_default())),
+            )
+            .field("tls_info", &self.tls_info(start_addr.unwrap_or_default()))
+            .finish()
+    }
+}
+
+/// Converts a [`NoteError`] into the corresponding [`ParseKernelErrorKind`].
+fn note_error(err: NoteError) -> ParseKernelErrorKind {
+    match err {
+        NoteError::OutOfBounds => ParseKernelErrorKind::OutOfBounds(ElfRegion::Note),
+        NoteError::NameNotNulTerminated => ParseKernelErrorKind::NoteNameNotNulTerminated,
+        NoteError::NameNotUtf8 => ParseKernelErrorKind::NoteNameNotUtf8,
+    }
+}
+
+/// Returns an iterator over the notes of the note segment `ph`.
+fn segment_notes<'a>(
+    elf: &'a [u8],
+    segment: usize,
+    ph: &ProgramHeader,
+) -> Result<NoteIterator<'a>, ParseKernelError> {
+    let align = ph.p_align.max(1);
+    if !align.is_power_of_two() {
+        return Err(ParseKernelErrorKind::InvalidNoteAlignment { segment }.into());
+    }
+    let bytes = file_range(elf, ph.p_offset, ph.p_filesz).ok_or(
+        ParseKernelErrorKind::OutOfBounds(ElfRegion::Segment(segment)),
+    )?;
+    Ok(NoteIterator::new(bytes, align as usize))
+}
+
+/// An iterator over the notes of all note segments (`PT_NOTE`) of a [`KernelObject`].
+///
+/// See [`KernelObject::notes`].
+#[derive(Clone)]
+pub struct Notes<'a> {
+    elf: &'a [u8],
+    phs: core::iter::Enumerate<core::slice::Iter<'a, ProgramHeader>>,
+    notes: NoteIterator<'a>,
+}
+
+impl<'a> Notes<'a> {
+    fn new(elf: &'a [u8], phs: &'a [ProgramHeader]) -> Self {
+        Self {
+            elf,
+            phs: phs.iter().enumerate(),
+            notes: NoteIterator::new(&[], 1),
+        }
+    }
+
+    /// Checks that all notes can be parsed.
+    fn validate(&self) -> Result<(), ParseKernelError> {
+        for (segment, ph) in self.phs.clone() {
+            if ph.p_type == program_header::PT_NOTE {
+                for note in segment_notes(self.elf, segment, ph)? {
+                    note.map_err(note_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Notes<'a> {
+    type Item = Note<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(note) = self.notes.next() {
+                // Validated by `KernelObject::parse`
+                return note.ok();
+            }
+
+            let (segment, ph) = self
+                .phs
+                .find(|(_, ph)| ph.p_type == program_header::PT_NOTE)?;
+            self.notes = segment_notes(self.elf, segment, ph).ok()?;
+        }
+    }
+}
+
+/// An error returned when parsing a kernel ELF fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseKernelError(ParseKernelErrorKind);
+
+impl ParseKernelError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &ParseKernelErrorKind {
+        &self.0
+    }
+}
+
+impl From<ParseKernelErrorKind> for ParseKernelError {
+    #[inline]
+    fn from(kind: ParseKernelErrorKind) -> Self {
+        Self(kind)
+    }
+}
+
+impl fmt::Display for ParseKernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = &self.0;
+        write!(f, "invalid ELF: {info}")
+    }
+}
+
+impl Error for ParseKernelError {}
+
+/// The kind of a [`ParseKernelError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseKernelErrorKind {
+    /// The file does not start with the ELF magic number.
+    NotElf,
+
+    /// The ELF file class is not `ELFCLASS64`.
+    UnsupportedClass(u8),
+
+    /// The ELF data encoding does not match the architecture's endianness.
+    ///
+    /// Kernel objects are read in native byte order, so the data encoding also has to match the loader's endianness.
+    UnsupportedDataEncoding(u8),
+
+    /// The ELF file type is neither `ET_EXEC` nor `ET_DYN`.
+    UnsupportedType(u16),
+
+    /// The kernel is compiled for an architecture not supported by Hermit.
+    UnsupportedArchitecture {
+        /// The `e_machine` of the kernel.
+        e_machine: u16,
+    },
+
+    /// The kernel is compiled for a different architecture than requested.
+    WrongArchitecture {
+        /// The `e_machine` of the kernel.
+        found: u16,
+
+        /// The requested architecture.
+        expected: Arch,
+    },
+
+    /// The size of the entries of a table does not match the ELF specification.
+    InvalidEntrySize(ElfRegion),
+
+    /// A region of the ELF file does not fit into the file.
+    OutOfBounds(ElfRegion),
+
+    /// A region of the ELF file is not sufficiently aligned in memory.
+    Misaligned(ElfRegion),
+
+    /// The kernel does not have any loadable segments.
+    NoLoadableSegments,
+
+    /// A loadable segment has a larger file size than memory size.
+    SegmentFileSizeExceedsMemSize {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// A loadable segment does not fit into the address space.
+    SegmentAddressOverflow {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// The loadable segments are not sorted by virtual address.
+    UnsortedSegments {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// A segment that has to be part of the loaded image is outside of it.
+    SegmentOutsideImage {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// The TLS segment's alignment is not a power of two.
+    InvalidTlsAlignment {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// The kernel does not have a note segment.
+    NoNoteSegment,
+
+    /// A note segment's alignment is not a power of two.
+    InvalidNoteAlignment {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// A note's name is not NUL-terminated.
+    NoteNameNotNulTerminated,
+
+    /// A note's name is not valid UTF-8.
+    NoteNameNotUtf8,
+
+    /// The kernel does not specify the Hermit entry version.
+    MissingEntryVersion,
+
+    /// The kernel's Hermit entry version is not supported.
+    EntryVersionMismatch {
+        /// The Hermit entry version of the kernel.
+        found: u8,
+
+        /// The Hermit entry versions supported by this loader.
+        supported: RangeInclusive<u8>,
+    },
+
+    /// The kernel was linked against dynamic libraries.
+    DynamicallyLinked,
+
+    /// A region referenced by the dynamic section is not inside a loadable segment.
+    Unmapped(ElfRegion),
+
+    /// The kernel has a relocation of an unsupported type.
+    UnsupportedRelocation {
+        /// The `r_type` of the relocation.
+        r_type: u32,
+    },
+}
+
+impl fmt::Display for ParseKernelErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotElf => f.write_str("kernel is not an ELF file"),
+            Self::UnsupportedClass(class) => {
+                write!(f, "kernel is not a 64-bit object (class {class})")
+            }
+            Self::UnsupportedDataEncoding(data_encoding) => {
+                write!(
+                    f,
+                    "kernel object does not have the architecture's native endianness (data encoding {data_encoding})"
+                )
+            }
+            Self::UnsupportedType(ty) => write!(f, "kernel has unsupported ELF type {ty}"),
+            Self::UnsupportedArchitecture { e_machine } => write!(
+                f,
+                "kernel is compiled for unsupported architecture {}",
+                header::machine_to_str(*e_machine)
+            ),
+            Self::WrongArchitecture { found, expected } => write!(
+                f,
+                "kernel is compiled for {} instead of {expected}",
+                header::machine_to_str(*found),
+            ),
+            Self::InvalidEntrySize(region) => write!(f, "{region} entry size is invalid"),
+            Self::OutOfBounds(region) => write!(f, "{region} is out of bounds"),
+            Self::Misaligned(region) => write!(f, "{region} is misaligned"),
+            Self::NoLoadableSegments => f.write_str("kernel does not have loadable segments"),
+            Self::SegmentFileSizeExceedsMemSize { segment } => {
+                write!(
+                    f,
+                    "segment {segment} has a file size exceeding its memory size"
+                )
+            }
+            Self::SegmentAddressOverflow { segment } => {
+                write!(f, "segment {segment} exceeds the address space")
+            }
+            Self::UnsortedSegments { segment } => {
+                write!(f, "segment {segment} is not sorted by address")
+            }
+            Self::SegmentOutsideImage { segment } => {
+                write!(f, "segment {segment} is outside of the loaded image")
+            }
+            Self::InvalidTlsAlignment { segment } => {
+                write!(f, "TLS segment {segment} alignment is not a power of two")
+            }
+            Self::NoNoteSegment => f.write_str("kernel does not have note section"),
+            Self::InvalidNoteAlignment { segment } => {
+                write!(f, "segment {segment} alignment is not a power of two")
+            }
+            Self::NoteNameNotNulTerminated => f.write_str("note name is not NUL-terminated"),
+            Self::NoteNameNotUtf8 => f.write_str("note name is not valid UTF-8"),
+            Self::MissingEntryVersion => {
+                f.write_str("kernel does not specify hermit entry version")
+            }
+            Self::EntryVersionMismatch { found, supported } => write!(
+                f,
+                "hermit entry version {found} is not in supported range {}..={}",
+                supported.start(),
+                supported.end()
+            ),
+            Self::DynamicallyLinked => f.write_str("kernel was linked against dynamic libraries"),
+            Self::Unmapped(region) => write!(f, "{region} is not inside a loadable segment"),
+            Self::UnsupportedRelocation { r_type } => {
+                write!(f, "unsupported relocation type {r_type}")
+            }
+        }
+    }
+}
+
+/// A region of an ELF file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ElfRegion {
+    /// The ELF file header.
+    Header,
+
+    /// The program header table.
+    ProgramHeaders,
+
+    /// The section header table.
+    SectionHeaders,
+
+    /// The segment of the program header with the given index.
+    Segment(usize),
+
+    /// A note inside a note segment.
+    Note,
+
+    /// The dynamic section.
+    Dynamic,
+
+    /// The `DT_RELA` relocation table.
+    Rela,
+
+    /// The `DT_REL` relocation table.
+    Rel,
+
+    /// The `DT_RELR` relocation table.
+    Relr,
+
+    /// The dynamic symbol table.
+    DynamicSymbols,
+
+    /// The `DT_HASH` or `DT_GNU_HASH` symbol hash table.
+    SymbolHash,
+}
+
+impl fmt::Display for ElfRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header => f.write_str("ELF header"),
+            Self::ProgramHeaders => f.write_str("program header table"),
+            Self::SectionHeaders => f.write_str("section header table"),
+            Self::Segment(index) => write!(f, "segment {index}"),
+            Self::Note => f.write_str("note"),
+            Self::Dynamic => f.write_str("dynamic section"),
+            Self::Rela => f.write_str("RELA relocation table"),
+            Self::Rel => f.write_str("REL relocation table"),
+            Self::Relr => f.write_str("RELR relocation table"),
+            Self::DynamicSymbols => f.write_str("dynamic symbol table"),
+            Self::SymbolHash => f.write_str("symbol hash table"),
+        }
+    }
+}
+
+/// Returns the virtual address range spanned by the loadable segments.
+///
+/// The loadable segments have to be validated.
+fn image_range(phs: &[ProgramHeader]) -> Range<u64> {
+    let start = phs
+        .iter()
+        .find(|ph| ph.p_type == program_header::PT_LOAD)
+        .map(|ph| ph.p_vaddr)
+        .unwrap_or_default();
+    let end = phs
+        .iter()
+        .rev()
+        .find(|ph| ph.p_type == program_header::PT_LOAD)
+        .map(|ph| ph.p_vaddr + ph.p_memsz)
+        .unwrap_or_default();
+    start..end
+}
+
+/// Checks that the segment `ph` lies within `image`.
+fn check_inside_image(
+    segment: usize,
+    ph: &ProgramHeader,
+    image: &Range<u64>,
+) -> Result<(), ParseKernelError> {
+    let end = ph
+        .p_vaddr
+        .checked_add(ph.p_memsz)
+        .ok_or(ParseKernelErrorKind::SegmentAddressOverflow { segment })?;
+    if ph.p_vaddr < image.start || end > image.end {
+        return Err(ParseKernelErrorKind::SegmentOutsideImage { segment }.into());
+    }
+    Ok(())
+}
+
+/// Returns `len` bytes of `elf` starting at `offset` if they are in bounds.
+fn file_range(elf: &[u8], offset: u64, len: u64) -> Option<&[u8]> {
+    let offset = usize::try_from(offset).ok()?;
+    let len = usize::try_from(len).ok()?;
+    elf.get(offset..)?.get(..len)
+}
+
+/// Translates a virtual address into a file offset using the loadable segments.
+fn vaddr_to_offset(phs: &[ProgramHeader], vaddr: u64) -> Option<u64> {
+    phs.iter()
+        .filter(|ph| ph.p_type == program_header::PT_LOAD)
+        .find(|ph| (ph.p_vaddr..ph.p_vaddr + ph.p_filesz).contains(&vaddr))
+        .map(|ph| ph.p_offset + (vaddr - ph.p_vaddr))
+}
+
+/// Returns the value of the first dynamic entry with `tag`.
+fn dyn_val(dyns: &[Dyn], tag: u64) -> Option<u64> {
+    dyns.iter().find(|d| d.d_tag == tag).map(|d| d.d_val)
+}
+
+/// Returns the dynamic symbol table.
+///
+/// The table is located through `DT_SYMTAB` and sized through `DT_HASH` or `DT_GNU_HASH`.
+/// If the dynamic section does not describe the table, the `SHT_DYNSYM` section is used.
+fn dynamic_symbols<'a>(
+    elf: &'a [u8],
+    phs: &[ProgramHeader],
+    shs: &[SectionHeader],
+    dyns: &[Dyn],
+) -> Result<&'a [Sym], ParseKernelErrorKind> {
+    let region = ElfRegion::DynamicSymbols;
+
+    if let Some(vaddr) = dyn_val(dyns, dynamic::DT_SYMTAB) {
+        if dyn_val(dyns, dynamic::DT_SYMENT).is_some_and(|ent| ent != mem::size_of::<Sym>() as u64)
+        {
+            return Err(ParseKernelErrorKind::InvalidEntrySize(region));
+        }
+
+        let hash_table = |vaddr| {
+            vaddr_to_offset(phs, vaddr)
+                .and_then(|offset| elf.get(usize::try_from(offset).ok()?..))
+                .ok_or(ParseKernelErrorKind::Unmapped(ElfRegion::SymbolHash))
+        };
+        let count = if let Some(hash) = dyn_val(dyns, dynamic::DT_HASH) {
+            Some(hash::sym_count(hash_table(hash)?))
+        } else if let Some(gnu_hash) = dyn_val(dyns, dynamic::DT_GNU_HASH) {
+            Some(hash::gnu_sym_count(hash_table(gnu_hash)?))
+        } else {
+            None
+        };
+
+        if let Some(count) = count {
+            let count = count.ok_or(ParseKernelErrorKind::OutOfBounds(ElfRegion::SymbolHash))?;
+            let offset =
+                vaddr_to_offset(phs, vaddr).ok_or(ParseKernelErrorKind::Unmapped(region))?;
+            let bytes = (count as u64)
+                .checked_mul(mem::size_of::<Sym>() as u64)
+                .and_then(|len| file_range(elf, offset, len))
+                .ok_or(ParseKernelErrorKind::OutOfBounds(region))?;
+            return Sym::slice_from_bytes(bytes).map_err(|err| plain_error(err, region));
+        }
+    }
+
+    match shs
+        .iter()
+        .find(|section_header| section_header.sh_type == section_header::SHT_DYNSYM)
+    {
+        Some(sh) => {
+            let bytes = file_range(elf, sh.sh_offset, sh.sh_size)
+                .ok_or(ParseKernelErrorKind::OutOfBounds(region))?;
+            Sym::slice_from_bytes(bytes).map_err(|err| plain_error(err, region))
+        }
+        None => Ok(&[]),
+    }
+}
+
+/// Returns the table described by the dynamic section's address, size and entry size tags.
+fn dynamic_table<'a, T: Plain>(
+    elf: &'a [u8],
+    phs: &[ProgramHeader],
+    dyns: &[Dyn],
+    [addr_tag, size_tag, ent_tag]: [u64; 3],
+    region: ElfRegion,
+) -> Result<&'a [T], ParseKernelErrorKind> {
+    let dyn_val = |tag| dyn_val(dyns, tag);
+
+    let (Some(vaddr), Some(size @ 1..)) = (dyn_val(addr_tag), dyn_val(size_tag)) else {
+        return Ok(&[]);
+    };
+    if dyn_val(ent_tag).is_some_and(|ent| ent != mem::size_of::<T>() as u64) {
+        return Err(ParseKernelErrorKind::InvalidEntrySize(region));
+    }
+
+    let offset = vaddr_to_offset(phs, vaddr).ok_or(ParseKernelErrorKind::Unmapped(region))?;
+    let bytes = file_range(elf, offset, size).ok_or(ParseKernelErrorKind::OutOfBounds(region))?;
+    plain::slice_from_bytes_len(bytes, bytes.len() / mem::size_of::<T>())
+        .map_err(|err| plain_error(err, region))
+}
+
+fn plain_error(err: plain::Error, region: ElfRegion) -> ParseKernelErrorKind {
+    match err {
+        plain::Error::TooShort => ParseKernelErrorKind::OutOfBounds(region),
+        plain::Error::BadAlignment => ParseKernelErrorKind::Misaligned(region),
+    }
+}
+
+impl KernelObject<'_> {
+    /// Parses raw bytes of an ELF file into a loadable kernel object.
+    ///
+    /// The kernel has to be compiled for [`Arch::HOST`].
+    pub fn parse(elf: &[u8]) -> Result<KernelObject<'_>, ParseKernelError> {
+        Self::parse_with_arch(elf, Arch::HOST)
+    }
+
+    /// Parses raw bytes of an ELF file into a loadable kernel object for `arch`.
+    pub fn parse_with_arch(elf: &[u8], arch: Arch) -> Result<KernelObject<'_>, ParseKernelError> {
+        KernelObject::parse_inner(elf, Some(arch))
+    }
+
+    /// Parses raw bytes of an ELF file into a loadable kernel object for any supported architecture.
+    ///
+    /// The architecture is detected from the ELF header and can be queried using [`Self::arch`].
+    pub fn parse_any_arch(elf: &[u8]) -> Result<KernelObject<'_>, ParseKernelError> {
+        KernelObject::parse_inner(elf, None)
+    }
+
+    fn parse_inner(elf: &[u8], arch: Option<Arch>) -> Result<KernelObject<'_>, ParseKernelError> {
+        {
+            let range = elf.as_ptr_range();
+            let len = elf.len();
+            info!("Parsing kernel from ELF at {range:?} (len = {len:#x} B / {len} B)");
+        }
+
+        let header =
+            plain::from_bytes::<Header>(elf).map_err(|err| plain_error(err, ElfRegion::Header))?;
+
+        // General compatibility checks
+        {
+            if &header.e_ident[..header::SELFMAG] != header::ELFMAG {
+                return Err(ParseKernelErrorKind::NotElf.into());
+            }
+
+            let class = header.e_ident[header::EI_CLASS];
+            if class != header::ELFCLASS64 {
+                return Err(ParseKernelErrorKind::UnsupportedClass(class).into());
+            }
+            let os_abi = header.e_ident[header::EI_OSABI];
+            if os_abi != header::ELFOSABI_STANDALONE {
+                warn!("Kernel is not a hermit application");
+            }
+
+            if !matches!(header.e_type, header::ET_DYN | header::ET_EXEC) {
+                return Err(ParseKernelErrorKind::UnsupportedType(header.e_type).into());
+            }
+        }
+
+        let arch = match arch {
+            Some(arch) if arch.e_machine() != header.e_machine => {
+                return Err(ParseKernelErrorKind::WrongArchitecture {
+                    found: header.e_machine,
+                    expected: arch,
+                }
+                .into());
+            }
+            Some(arch) => arch,
+            None => Arch::from_e_machine(header.e_machine).ok_or(
+                ParseKernelErrorKind::UnsupportedArchitecture {
+                    e_machine: header.e_machine,
+                },
+            )?,
+        };
+        info!("Kernel is compiled for {arch}");
+
+        // Kernel objects are read in native byte order.
+        #[cfg(target_endian = "little")]
+        const NATIVE_DATA_ENCODING: u8 = header::ELFDATA2LSB;
+        #[cfg(target_endian = "big")]
+        const NATIVE_DATA_ENCODING: u8 = header::ELFDATA2MSB;
+
+        let data_encoding = header.e_ident[header::EI_DATA];
+        if data_encoding != arch.data_encoding() || data_encoding != NATIVE_DATA_ENCODING {
+            return Err(ParseKernelErrorKind::UnsupportedDataEncoding(data_encoding).into());
+        }
+
+        let phs = {
+            let len = usize::from(header.e_phnum);
+            if len != 0 && usize::from(header.e_phentsize) != mem::size_of::<ProgramHeader>() {
+                return Err(
+                    ParseKernelErrorKind::InvalidEntrySize(ElfRegion::ProgramHeaders).into(),
+                );
+            }
+            let bytes = usize::try_from(header.e_phoff)
+                .ok()
+                .and_then(|start| elf.get(start..))
+                .ok_or(ParseKernelErrorKind::OutOfBounds(ElfRegion::ProgramHeaders))?;
+            ProgramHeader::slice_from_bytes_len(bytes, len)
+                .map_err(|err| plain_error(err, ElfRegion::ProgramHeaders))?
+        };
+
+        let shs = {
+            let len = usize::from(header.e_shnum);
+            if len != 0 && usize::from(header.e_shentsize) != mem::size_of::<SectionHeader>() {
+                return Err(
+                    ParseKernelErrorKind::InvalidEntrySize(ElfRegion::SectionHeaders).into(),
+                );
+            }
+            let bytes = usize::try_from(header.e_shoff)
+                .ok()
+                .and_then(|start| elf.get(start..))
+                .ok_or(ParseKernelErrorKind::OutOfBounds(ElfRegion::SectionHeaders))?;
+            SectionHeader::slice_from_bytes_len(bytes, len)
+                .map_err(|err| plain_error(err, ElfRegion::SectionHeaders))?
+        };
+
+        // Loadable segment checks
+        {
+            let mut load_phs = phs
+                .iter()
+                .enumerate()
+                .filter(|(_, ph)| ph.p_type == program_header::PT_LOAD)
+                .peekable();
+            if load_phs.peek().is_none() {
+                return Err(ParseKernelErrorKind::NoLoadableSegments.into());
+            }
+
+            let mut last_vaddr = 0;
+            for (segment, ph) in load_phs {
+                if ph.p_filesz > ph.p_memsz {
+                    return Err(
+                        ParseKernelErrorKind::SegmentFileSizeExceedsMemSize { segment }.into(),
+                    );
+                }
+                if file_range(elf, ph.p_offset, ph.p_filesz).is_none() {
+                    return Err(
+                        ParseKernelErrorKind::OutOfBounds(ElfRegion::Segment(segment)).into(),
+                    );
+                }
+                if ph.p_vaddr.checked_add(ph.p_memsz).is_none() {
+                    return Err(ParseKernelErrorKind::SegmentAddressOverflow { segment }.into());
+                }
+                if ph.p_vaddr < last_vaddr {
+                    return Err(ParseKernelErrorKind::UnsortedSegments { segment }.into());
+                }
+                last_vaddr = ph.p_vaddr;
+            }
+        }
+
+        let image = image_range(phs);
+
+        // TLS segment checks
+        for (segment, ph) in phs
+            .iter()
+            .enumerate()
+            .filter(|(_, ph)| ph.p_type == program_header::PT_TLS)
+        {
+            if ph.p_filesz > ph.p_memsz {
+                return Err(ParseKernelErrorKind::SegmentFileSizeExceedsMemSize { segment }.into());
+            }
+            check_inside_image(segment, ph, &image)?;
+            if !ph.p_align.max(1).is_power_of_two() {
+                return Err(ParseKernelErrorKind::InvalidTlsAlignment { segment }.into());
+            }
+        }
+
+        // RELRO segment checks
+        for (segment, ph) in phs
+            .iter()
+            .enumerate()
+            .filter(|(_, ph)| ph.p_type == program_header::PT_GNU_RELRO)
+        {
+            check_inside_image(segment, ph, &image)?;
+        }
+
+        if !phs.iter().any(|ph| ph.p_type == program_header::PT_NOTE) {
+            return Err(ParseKernelErrorKind::NoNoteSegment.into());
+        }
+        // Linkers emit separate note segments for notes with different alignments.
+        let mut note_iter = Notes::new(elf, phs);
+        note_iter.validate()?;
+
+        let hermit_version = note_iter.clone().find_map(HermitVersion::from_note);
+        if let Some(hermit_version) = hermit_version {
+            info!("Found Hermit version {hermit_version}");
+        }
+
+        let uhyve_interface_version: Option<UhyveIfVersion> =
+            note_iter.clone().find_map(UhyveIfVersion::from_note);
+        if let Some(uhyve_interface_version) = uhyve_interface_version {
+            info!("Found Uhyve interface version {uhyve_interface_version}");
+        }
+
+        let requirements = note_iter.clone().find_map(KernelRequirements::from_note);
+        if let Some(requirements) = requirements {
+            info!("Found kernel requirements {requirements:?}");
+        }
+
+        // The default command line is only advisory.
+        let default_cmdline = match note_iter.clone().find_map(|note| note.default_cmdline()) {
+            Some(Ok(default_cmdline)) => {
+                info!("Found default command line {default_cmdline:?}");
+                Some(default_cmdline)
+            }
+            Some(Err(_)) => {
+                warn!("Ignoring default command line that is not valid UTF-8");
+                None
+            }
+            None => None,
+        };
+
+        let entry_version = note_iter
+            .find_map(|note| note.entry_version())
+            .flatten()
+            .ok_or(ParseKernelErrorKind::MissingEntryVersion)?;
+        if !SUPPORTED_ENTRY_VERSIONS.contains(&entry_version) {
+            return Err(ParseKernelErrorKind::EntryVersionMismatch {
+                found: entry_version,
+                supported: SUPPORTED_ENTRY_VERSIONS,
+            }
+            .into());
+        }
+        if entry_version != crate::HERMIT_ENTRY_VERSION {
+            info!("Kernel uses hermit entry version {entry_version}");
+        }
+
+        let dyns = match phs
+            .iter()
+            .find(|program_header| program_header.p_type == program_header::PT_DYNAMIC)
+        {
+            Some(ph) => {
+                let bytes = file_range(elf, ph.p_offset, ph.p_filesz)
+                    .ok_or(ParseKernelErrorKind::OutOfBounds(ElfRegion::Dynamic))?;
+                Dyn::slice_from_bytes(bytes).map_err(|err| plain_error(err, ElfRegion::Dynamic))?
+            }
+            None => &[],
+        };
+
+        if dyns.iter().any(|d| d.d_tag == dynamic::DT_NEEDED) {
+            return Err(ParseKernelErrorKind::DynamicallyLinked.into());
+        }
+
+        let relas = dynamic_table::<Rela>(
+            elf,
+            phs,
+            dyns,
+            [dynamic::DT_RELA, dynamic::DT_RELASZ, dynamic::DT_RELAENT],
+            ElfRegion::Rela,
+        )?;
+        let rels = dynamic_table::<Rel>(
+            elf,
+            phs,
+            dyns,
+            [dynamic::DT_REL, dynamic::DT_RELSZ, dynamic::DT_RELENT],
+            ElfRegion::Rel,
+        )?;
+        let relrs = dynamic_table::<Relr>(
+            elf,
+            phs,
+            dyns,
+            [relr::DT_RELR, relr::DT_RELRSZ, relr::DT_RELRENT],
+            ElfRegion::Relr,
+        )?;
+
+        if header.e_type == header::ET_DYN {
+            let r_infos = relas
+                .iter()
+                .map(|rela| rela.r_info)
+                .chain(rels.iter().map(|rel| rel.r_info));
+            for r_info in r_infos {
+                let r_type = reloc::r_type(r_info);
+                if arch.relocation_kind(r_type).is_none() {
+                    return Err(ParseKernelErrorKind::UnsupportedRelocation { r_type }.into());
+                }
+            }
+        }
+
+        let dynsyms = dynamic_symbols(elf, phs, shs, dyns)?;
+
+        Ok(KernelObject {
+            elf,
+            header,
+            arch,
+            phs,
+            relas,
+            rels,
+            relrs,
+            dynsyms,
+            hermit_version,
+            entry_version,
+            uhyve_interface_version,
+            requirements,
+            default_cmdline,
+        })
+    }
+
+    /// Returns the architecture this kernel is compiled for.
+    pub fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the Hermit entry version of this kernel.
+    ///
+    /// This determines the [`RawBootInfo`] layout expected by the kernel.
+    /// Kernels with entry version 3 expect [`RawBootInfoV3`].
+    /// Kernels with entry version 4 expect [`RawBootInfoV4`].
+    ///
+    /// [`RawBootInfo`]: crate::boot_info::RawBootInfo
+    /// [`RawBootInfoV3`]: crate::boot_info::RawBootInfoV3
+    /// [`RawBootInfoV4`]: crate::boot_info::RawBootInfoV4
+    pub fn entry_version(&self) -> u8 {
+        self.entry_version
+    }
+
+    /// Returns the Hermit version of this kernel if present.
+    pub fn hermit_version(&self) -> Option<HermitVersion> {
+        self.hermit_version
+    }
+
+    /// Returns the Hermit version of this kernel if present.
+    pub fn uhyve_interface_version(&self) -> Option<UhyveIfVersion> {
+        self.uhyve_interface_version
+    }
+
+    /// Returns the requirements declared by this kernel if present.
+    pub fn requirements(&self) -> Option<KernelRequirements> {
+        self.requirements
+    }
+
+    /// Returns the default command line of this kernel if present and valid UTF-8.
+    ///
+    /// Loaders can merge this with user-supplied arguments.
+    pub fn default_cmdline(&self) -> Option<&str> {
+        self.default_cmdline
+    }
+
+    /// Returns the kernel's loadable segments.
+    ///
+    /// Loaders can use this to map each segment with its own permissions.
+    pub fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.phs
+            .iter()
+            .filter(|ph| ph.p_type == program_header::PT_LOAD)
+            .map(Segment::from_ph)
+    }
+
+    /// Returns the virtual address range that can be made read-only after relocating (`PT_GNU_RELRO`).
+    ///
+    /// For relocatable kernels, this is relative to the load address.
+    pub fn relro(&self) -> Option<Range<u64>> {
+        self.phs
+            .iter()
+            .find(|ph| ph.p_type == program_header::PT_GNU_RELRO)
+            // Validated by `KernelObject::parse`
+            .map(|ph| ph.p_vaddr..ph.p_vaddr + ph.p_memsz)
+    }
+
+    /// Returns the permissions requested for the stack (`PT_GNU_STACK`).
+    pub fn stack_flags(&self) -> Option<SegmentFlags> {
+        self.phs
+            .iter()
+            .find(|ph| ph.p_type == program_header::PT_GNU_STACK)
+            .map(|ph| SegmentFlags(ph.p_flags))
+    }
+
+    /// Returns an iterator over the notes of all note segments.
+    pub fn notes(&self) -> Notes<'_> {
+        Notes::new(self.elf, self.phs)
+    }
+
+    /// Returns the descriptor of the first note with the given name and type.
+    ///
+    /// This can be used to read notes defined by `define_note!`.
+    pub fn find_note(&self, name: &str, ty: u32) -> Option<&[u8]> {
+        self.notes()
+            .find(|note| note.name == name && note.ty == ty)
+            .map(|note| note.desc)
+    }
+
+    /// Returns the kernel's build ID (`NT_GNU_BUILD_ID`), if any.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.find_note("GNU", note::NT_GNU_BUILD_ID)
+    }
+
+    /// Returns the maximum alignment of the kernel's loadable segments.
+    ///
+    /// Relocatable kernels have to be loaded at a multiple of this alignment.
+    pub fn max_align(&self) -> u64 {
+        self.phs
+            .iter()
+            .filter(|ph| ph.p_type == program_header::PT_LOAD)
+            .map(|ph| ph.p_align)
+            .max()
+            .unwrap_or_default()
+            .max(1)
+    }
+
+    /// Chooses a randomized start address for loading the kernel.
+    ///
+    /// The kernel image is placed within one of `windows` without overlapping any of `reserved`.
+    /// The address is a multiple of [`Self::max_align`] and is derived from `random`, which should be uniformly distributed.
+    /// Kernels that are not relocatable can only be loaded at [`Self::start_addr`].
+    ///
+    /// Returns [`None`] if the kernel does not fit.
+    pub fn choose_start_addr(
+        &self,
+        windows: &[Range<u64>],
+        reserved: &[Range<u64>],
+        random: u64,
+    ) -> Option<u64> {
+        let free = kaslr::free_ranges(windows, reserved);
+        let size = self.mem_size() as u64;
+
+        if let Some(start_addr) = self.start_addr() {
+            let end_addr = start_addr.checked_add(size)?;
+            return free
+                .iter()
+                .any(|range| range.start <= start_addr && end_addr <= range.end)
+                .then_some(start_addr);
+        }
+
+        let start_addr = kaslr::choose(&free, size, self.max_align(), random)?;
+        info!("Chose randomized start address {start_addr:#x}");
+        Some(start_addr)
+    }
+
+    /// Required memory size for loading.
+    pub fn mem_size(&self) -> usize {
+        let first_ph = self
+            .phs
+            .iter()
+            .find(|ph| ph.p_type == program_header::PT_LOAD)
+            .unwrap();
+        let start_addr = first_ph.p_vaddr;
+
+        let last_ph = self
+            .phs
+            .iter()
+            .rev()
+            .find(|ph| ph.p_type == program_header::PT_LOAD)
+            .unwrap();
+        let end_addr = last_ph.p_vaddr + last_ph.p_memsz;
+
+        let mem_size = end_addr - start_addr;
+        mem_size.try_into().unwrap()
+    }
+
+    fn is_relocatable(&self) -> bool {
+        match self.header.e_type {
+            header::ET_DYN => true,
+            header::ET_EXEC => false,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the required start address.
+    ///
+    /// If this returns [`None`], the kernel is relocatable and does not require a certain start address.
+    pub fn start_addr(&self) -> Option<u64> {
+        (!self.is_relocatable()).then(|| {
+            self.phs
+                .iter()
+                .find(|ph| ph.p_type == program_header::PT_LOAD)
+                .unwrap()
+                .p_vaddr
+        })
+    }
+
+    fn tls_info(&self, start_addr: u64) -> Option<TlsInfo> {
+        self.phs
+            .iter()
+            .find(|ph| ph.p_type == program_header::PT_TLS)
+            .map(|ph| {
+                let mut tls_start = ph.p_vaddr;
+                if self.is_relocatable() {
+                    tls_start = tls_start.wrapping_add(start_addr);
+                }
+                TlsInfo {
+                    start: tls_start,
+                    filesz: ph.p_filesz,
+                    memsz: ph.p_memsz,
+                    align: ph.p_align,
+                }
+            })
+    }
+
+    fn entry_point(&self, start_addr: u64) -> u64 {
+        let mut entry_point = self.header.e_entry;
+        if self.is_relocatable() {
+            entry_point = entry_point.wrapping_add(start_addr);
+        }
+        entry_point
+    }
+
+    /// Returns the kernel's dynamic relocations.
+    ///
+    /// `DT_RELA` relocations come first, followed by `DT_REL` and `DT_RELR` relocations.
+    /// This is the order in which [`Self::load_kernel`] applies them.
+    pub fn relocations(&self) -> impl Iterator<Item = Relocation> + '_ {
+        let relas = self.relas.iter().map(|rela| Relocation {
+            r_type: reloc::r_type(rela.r_info),
+            offset: rela.r_offset,
+            sym: reloc::r_sym(rela.r_info),
+            addend: Some(rela.r_addend),
+        });
+        let rels = self.rels.iter().map(|rel| Relocation {
+            r_type: reloc::r_type(rel.r_info),
+            offset: rel.r_offset,
+            sym: reloc::r_sym(rel.r_info),
+            addend: None,
+        });
+        let relrs = RelrIter::new(self.relrs).map(|offset| Relocation {
+            r_type: self.arch.relative_r_type(),
+            offset,
+            sym: 0,
+            addend: None,
+        });
+        relas.chain(rels).chain(relrs)
+    }
+
+    /// Applies a single relocation to the loaded image.
+    ///
+    /// This is what [`DefaultRelocationHandler`] does.
+    /// `sink` has to contain the loaded kernel at `start_addr`.
+    pub fn apply_relocation(
+        &self,
+        sink: &mut dyn KernelSink,
+        start_addr: u64,
+        relocation: &Relocation,
+    ) -> Result<(), LoadKernelError> {
+        let Relocation {
+            r_type,
+            offset,
+            sym,
+            addend,
+        } = *relocation;
+        let addend = match addend {
+            Some(addend) => addend,
+            None => self.read_relocation_target(sink, start_addr, offset)? as i64,
+        };
+
+        let kind = self
+            .arch
+            .relocation_kind(r_type)
+            .ok_or(LoadKernelErrorKind::UnsupportedRelocation { r_type })?;
+        let value = match kind {
+            RelocationKind::Abs64 | RelocationKind::GlobDat | RelocationKind::JumpSlot => {
+                let sym = self.relocation_symbol(sym)?;
+
+                if sym.bind == STB_WEAK && !sym.defined {
+                    if self.read_relocation_target(sink, start_addr, offset)? != 0 {
+                        return Err(LoadKernelErrorKind::UnresolvedWeakSymbol { offset }.into());
+                    }
+                    return Ok(());
+                }
+
+                if self.arch == Arch::X86_64 && kind != RelocationKind::Abs64 && addend != 0 {
+                    return Err(LoadKernelErrorKind::UnexpectedAddend { offset }.into());
+                }
+
+                start_addr
+                    .wrapping_add(sym.value)
+                    .wrapping_add_signed(addend)
+            }
+            // For `R_*_IRELATIVE`, this is the address of the resolver.
+            RelocationKind::Relative | RelocationKind::IRelative => {
+                start_addr.wrapping_add_signed(addend)
+            }
+            RelocationKind::TlsDtpMod => {
+                // The kernel is the only module.
+                1
+            }
+            RelocationKind::TlsDtpOff => self
+                .tls_symbol_value(sym)?
+                .wrapping_add_signed(addend)
+                .wrapping_sub(self.arch.dtv_offset()),
+            RelocationKind::TlsTpOff => {
+                let tls = self
+                    .tls_info(start_addr)
+                    .ok_or(LoadKernelErrorKind::MissingTls { offset })?;
+                let tls_block_offset = self
+                    .arch
+                    .tls_block_offset(tls.memsz, tls.align)
+                    .ok_or(LoadKernelErrorKind::TlsOverflow { offset })?;
+                self.tls_symbol_value(sym)?
+                    .wrapping_add_signed(addend)
+                    .wrapping_add_signed(tls_block_offset)
+            }
+        };
+
+        self.relocation_target(offset)?;
+        sink.write(start_addr.wrapping_add(offset), &value.to_le_bytes())
+    }
+
+    /// Checks that a relocation target at `offset` is within the kernel image.
+    fn relocation_target(&self, offset: u64) -> Result<(), LoadKernelError> {
+        offset
+            .checked_add(mem::size_of::<u64>() as u64)
+            .filter(|&end| end <= self.mem_size() as u64)
+            .map(drop)
+            .ok_or(LoadKernelErrorKind::RelocationOutOfBounds { offset }.into())
+    }
+
+    /// Reads the 8 bytes at `offset` which are targeted by a relocation.
+    fn read_relocation_target(
+        &self,
+        sink: &mut dyn KernelSink,
+        start_addr: u64,
+        offset: u64,
+    ) -> Result<u64, LoadKernelError> {
+        self.relocation_target(offset)?;
+        let mut target = [0; mem::size_of::<u64>()];
+        sink.read(start_addr.wrapping_add(offset), &mut target)?;
+        Ok(u64::from_le_bytes(target))
+    }
+
+    /// Returns the dynamic symbol with index `index`.
+    ///
+    /// This is the symbol referenced by [`Relocation::sym`].
+    pub fn symbol(&self, index: u32) -> Option<Symbol> {
+        let sym = self.dynsyms.get(index as usize)?;
+        Some(Symbol {
+            value: sym.st_value,
+            bind: sym::st_bind(sym.st_info),
+            defined: u32::from(sym.st_shndx) != SHN_UNDEF,
+        })
+    }
+
+    /// Returns the dynamic symbol with index `index` for a relocation.
+    fn relocation_symbol(&self, index: u32) -> Result<Symbol, LoadKernelError> {
+        self.symbol(index).ok_or(
+            LoadKernelErrorKind::SymbolOutOfBounds {
+                index: index as usize,
+            }
+            .into(),
+        )
+    }
+
+    /// Returns the offset of a relocation's TLS symbol in the TLS block.
+    ///
+    /// Relocations without a symbol refer to the start of the TLS block.
+    fn tls_symbol_value(&self, sym: u32) -> Result<u64, LoadKernelError> {
+        match sym {
+            0 => Ok(0),
+            index => Ok(self.relocation_symbol(index)?.value),
+        }
+    }
+
+    /// Loads the kernel into the provided memory.
+    ///
+    /// `memory` has to be exactly [`Self::mem_size`] bytes long.
+    /// If the kernel is not relocatable, `start_addr` has to be [`Self::start_addr`].
+    pub fn load_kernel(
+        &self,
+        memory: &mut [MaybeUninit<u8>],
+        start_addr: u64,
+    ) -> Result<LoadedKernel, LoadKernelError> {
+        self.load_kernel_with(memory, start_addr, &mut DefaultRelocationHandler)
+    }
+
+    /// Loads the kernel into the provided memory, passing each relocation to `handler`.
+    ///
+    /// See [`Self::load_kernel`].
+    pub fn load_kernel_with(
+        &self,
+        memory: &mut [MaybeUninit<u8>],
+        start_addr: u64,
+        handler: &mut dyn RelocationHandler,
+    ) -> Result<LoadedKernel, LoadKernelError> {
+        info!(
+            "Loading kernel to {:?} (len = {len:#x} B / {len} B)",
+            memory.as_ptr_range(),
+            len = memory.len()
+        );
+
+        if self.mem_size() != memory.len() {
+            return Err(LoadKernelErrorKind::WrongMemorySize {
+                found: memory.len(),
+                expected: self.mem_size(),
+            }
+            .into());
+        }
+
+        let mut sink = MemorySink { memory, start_addr };
+        self.load_kernel_into(&mut sink, start_addr, handler)
+    }
+
+    /// Loads the kernel through `sink`, passing each relocation to `handler`.
+    ///
+    /// The kernel image occupies [`Self::mem_size`] bytes at `start_addr`.
+    /// Every byte of the image is written or zeroed before relocations are applied.
+    /// If the kernel is not relocatable, `start_addr` has to be [`Self::start_addr`].
+    pub fn load_kernel_into(
+        &self,
+        sink: &mut dyn KernelSink,
+        start_addr: u64,
+        handler: &mut dyn RelocationHandler,
+    ) -> Result<LoadedKernel, LoadKernelError> {
+        if let Some(expected) = self.start_addr()
+            && expected != start_addr
+        {
+            return Err(LoadKernelErrorKind::WrongStartAddress {
+                found: start_addr,
+                expected,
+            }
+            .into());
+        }
+
+        // Load program segments
+        // Contains TLS initialization image
+        let load_start_addr = self.start_addr().unwrap_or_default();
+        let mem_size = self.mem_size() as u64;
+        let end_addr = start_addr
+            .checked_add(mem_size)
+            .ok_or(LoadKernelErrorKind::AddressOverflow { start_addr })?;
+        let mut initialized_end = 0;
+        for (segment, ph) in self
+            .phs
+            .iter()
+            .enumerate()
+            .filter(|(_, ph)| ph.p_type == program_header::PT_LOAD)
+        {
+            let mem_start = ph
+                .p_vaddr
+                .checked_sub(load_start_addr)
+                .filter(|mem_start| {
+                    mem_start
+                        .checked_add(ph.p_memsz)
+                        .is_some_and(|mem_end| mem_end <= mem_size)
+                })
+                .ok_or(LoadKernelErrorKind::SegmentOutOfBounds { segment })?;
+
+            // Zero any gap between segments to keep the whole image initialized.
+            if initialized_end < mem_start {
+                sink.zero(
+                    start_addr.wrapping_add(initialized_end),
+                    mem_start - initialized_end,
+                )?;
+            }
+
+            // Validated by `KernelObject::parse`
+            let ph_file = file_range(self.elf, ph.p_offset, ph.p_filesz).unwrap();
+            let addr = start_addr.wrapping_add(mem_start);
+            sink.write(addr, ph_file)?;
+            sink.zero(addr.wrapping_add(ph.p_filesz), ph.p_memsz - ph.p_filesz)?;
+
+            initialized_end = initialized_end.max(mem_start + ph.p_memsz);
+        }
+        if initialized_end < mem_size {
+            sink.zero(
+                start_addr.wrapping_add(initialized_end),
+                mem_size - initialized_end,
+            )?;
+        }
+
+        let mut irelative_relocations = Vec::new();
+        if self.is_relocatable() {
+            for relocation in self.relocations() {
+                handler.relocate(self, sink, start_addr, &relocation)?;
+
+                if self.arch.relocation_kind(relocation.r_type) == Some(RelocationKind::IRelative) {
+                    irelative_relocations.push(IRelativeRelocation {
+                        addr: start_addr.wrapping_add(relocation.offset),
+                        resolver: self.read_relocation_target(
+                            sink,
+                            start_addr,
+                            relocation.offset,
+                        )?,
+                    });
+                }
+            }
+        }
+
+        let tls_info = self.tls_info(start_addr);
+
+        if let Some(tls_info) = &tls_info {
+            let range = tls_info.start as *const ()..(tls_info.start + tls_info.memsz) as *const ();
+            let len = tls_info.memsz;
+            info!("TLS is at {range:?} (len =  {len:#x} B / {len} B)",);
+        }
+
+        let load_offset = if self.is_relocatable() { start_addr } else { 0 };
+        Ok(LoadedKernel {
+            load_info: LoadInfo {
+                kernel_image_addr_range: start_addr..end_addr,
+                tls_info,
+                initrds: &[],
+                irelative_relocations: &[],
+            },
+            entry_point: self.entry_point(start_addr),
+            irelative_relocations,
+            segments: self
+                .segments()
+                .map(|segment| segment.offset(load_offset))
+                .collect(),
+            entry_version: self.entry_version,
+            hermit_version: self.hermit_version,
+            uhyve_interface_version: self.uhyve_interface_version,
+            requirements: self.requirements,
+            default_cmdline: self.default_cmdline.map(String::from),
+            build_id: self.build_id().map(<[u8]>::to_vec),
+        })
+    }
+}
+
+/// A [`KernelSink`] for contiguous memory.
+struct MemorySink<'a> {
+    memory: &'a mut [MaybeUninit<u8>],
+    start_addr: u64,
+}
+
+impl MemorySink<'_> {
+    fn get_mut(
+        &mut self,
+        addr: u64,
+        len: usize,
+    ) -> Result<&mut [MaybeUninit<u8>], LoadKernelError> {
+        addr.checked_sub(self.start_addr)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .and_then(|offset| self.memory.get_mut(offset..)?.get_mut(..len))
+            .ok_or(LoadKernelErrorKind::InaccessibleMemory { addr }.into())
+    }
+}
+
+impl KernelSink for MemorySink<'_> {
+    fn write(&mut self, addr: u64, bytes: &[u8]) -> Result<(), LoadKernelError> {
+        // FIXME: Replace with `maybe_uninit_write_slice` once stable
+        let bytes = unsafe { mem::transmute::<&[u8], &[MaybeUninit<u8>]>(bytes) };
+        self.get_mut(addr, bytes.len())?.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn zero(&mut self, addr: u64, len: u64) -> Result<(), LoadKernelError> {
+        let len =
+            usize::try_from(len).map_err(|_| LoadKernelErrorKind::InaccessibleMemory { addr })?;
+        for byte in self.get_mut(addr, len)? {
+            byte.write(0);
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, addr: u64, bytes: &mut [u8]) -> Result<(), LoadKernelError> {
+        let memory = self.get_mut(addr, bytes.len())?;
+        // SAFETY: The whole image is initialized before relocating.
+        let memory = unsafe { mem::transmute::<&[MaybeUninit<u8>], &[u8]>(memory) };
+        bytes.copy_from_slice(memory);
+        Ok(())
+    }
+}
+
+/// An error returned when loading a kernel fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadKernelError(LoadKernelErrorKind);
+
+impl LoadKernelError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &LoadKernelErrorKind {
+        &self.0
+    }
+}
+
+impl From<LoadKernelErrorKind> for LoadKernelError {
+    #[inline]
+    fn from(kind: LoadKernelErrorKind) -> Self {
+        Self(kind)
+    }
+}
+
+impl fmt::Display for LoadKernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = &self.0;
+        write!(f, "could not load kernel: {info}")
+    }
+}
+
+impl Error for LoadKernelError {}
+
+/// The kind of a [`LoadKernelError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadKernelErrorKind {
+    /// The provided memory does not have the size of [`KernelObject::mem_size`].
+    WrongMemorySize {
+        /// The size of the provided memory.
+        found: usize,
+
+        /// The required memory size.
+        expected: usize,
+    },
+
+    /// The kernel is not relocatable and the start address is not [`KernelObject::start_addr`].
+    WrongStartAddress {
+        /// The provided start address.
+        found: u64,
+
+        /// The required start address.
+        expected: u64,
+    },
+
+    /// A loadable segment does not fit into the provided memory.
+    SegmentOutOfBounds {
+        /// The index of the offending program header.
+        segment: usize,
+    },
+
+    /// The kernel has a relocation of an unsupported type.
+    UnsupportedRelocation {
+        /// The `r_type` of the relocation.
+        r_type: u32,
+    },
+
+    /// A relocation targets memory outside of the kernel image.
+    RelocationOutOfBounds {
+        /// The `r_offset` of the relocation.
+        offset: u64,
+    },
+
+    /// A relocation refers to a symbol outside of the symbol table.
+    SymbolOutOfBounds {
+        /// The index of the symbol.
+        index: usize,
+    },
+
+    /// A relocation against an undefined weak symbol targets non-zero memory.
+    UnresolvedWeakSymbol {
+        /// The `r_offset` of the relocation.
+        offset: u64,
+    },
+
+    /// A relocation has an addend although its type does not allow one.
+    UnexpectedAddend {
+        /// The `r_offset` of the relocation.
+        offset: u64,
+    },
+
+    /// A [`KernelSink`] could not access memory.
+    InaccessibleMemory {
+        /// The address of the access.
+        addr: u64,
+    },
+
+    /// A [`RelocationHandler`] rejected a relocation.
+    RejectedRelocation {
+        /// The offset of the relocation.
+        offset: u64,
+    },
+
+    /// A TLS relocation was found, but the kernel does not have a TLS segment.
+    MissingTls {
+        /// The `r_offset` of the relocation.
+        offset: u64,
+    },
+
+    /// The offset of the TLS block of a TLS relocation does not fit into 64 bits.
+    TlsOverflow {
+        /// The `r_offset` of the relocation.
+        offset: u64,
+    },
+
+    /// The kernel image exceeds the address space at the provided start address.
+    AddressOverflow {
+        /// The provided start address.
+        start_addr: u64,
+    },
+}
+
+impl fmt::Display for LoadKernelErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongMemorySize { found, expected } => {
+                write!(f, "memory size is {found:#x} B instead of {expected:#x} B")
+            }
+            Self::WrongStartAddress { found, expected } => {
+                write!(f, "start address is {found:#x} instead of {expected:#x}")
+            }
+            Self::SegmentOutOfBounds { segment } => {
+                write!(f, "segment {segment} does not fit into the kernel image")
+            }
+            Self::UnsupportedRelocation { r_type } => {
+                write!(f, "unsupported relocation type {r_type}")
+            }
+            Self::RelocationOutOfBounds { offset } => {
+                write!(
+                    f,
+                    "relocation at {offset:#x} is outside of the kernel image"
+                )
+            }
+            Self::SymbolOutOfBounds { index } => {
+                write!(f, "symbol {index} is outside of the symbol table")
+            }
+            Self::UnresolvedWeakSymbol { offset } => write!(
+                f,
+                "relocation at {offset:#x} against undefined weak symbol targets non-zero memory"
+            ),
+            Self::UnexpectedAddend { offset } => {
+                write!(f, "relocation at {offset:#x} has an unexpected addend")
+            }
+            Self::InaccessibleMemory { addr } => {
+                write!(f, "memory at {addr:#x} is inaccessible")
+            }
+            Self::RejectedRelocation { offset } => {
+                write!(f, "relocation at {offset:#x} was rejected")
+            }
+            Self::MissingTls { offset } => {
+                write!(f, "TLS relocation at {offset:#x} without TLS segment")
+            }
+            Self::TlsOverflow { offset } => {
+                write!(f, "TLS block offset of relocation at {offset:#x} overflows")
+            }
+            Self::AddressOverflow { start_addr } => {
+                write!(
+                    f,
+                    "kernel image at {start_addr:#x} exceeds the address space"
+                )
+            }
+        }
+    }
+}
+
+/// Load information required by the loader.
+#[derive(Debug)]
+pub struct LoadedKernel {
+    /// Load information required by the kernel.
+    ///
+    /// **[`LoadInfo::irelative_relocations`] is always empty here.**
+    /// Forwarding this to the kernel drops [`Self::irelative_relocations`],
+    /// and the kernel would call resolvers instead of the resolved functions.
+    /// Use [`Self::load_info_with`] instead.
+    pub load_info: LoadInfo,
+
+    /// The kernel's entry point.
+    pub entry_point: u64,
+
+    /// `R_*_IRELATIVE` relocations that the kernel has to resolve.
+    ///
+    /// See [`Self::load_info_with`] for passing these to the kernel.
+    pub irelative_relocations: Vec<IRelativeRelocation>,
+
+    /// The kernel's loadable segments at their load addresses.
+    pub segments: Vec<Segment>,
+
+    /// The kernel's Hermit entry version.
+    pub entry_version: u8,
+
+    /// The kernel's Hermit version if present.
+    pub hermit_version: Option<HermitVersion>,
+
+    /// The kernel's Uhyve interface version if present.
+    pub uhyve_interface_version: Option<UhyveIfVersion>,
+
+    /// The kernel's requirements if present.
+    pub requirements: Option<KernelRequirements>,
+
+    /// The kernel's default command line if present.
+    pub default_cmdline: Option<String>,
+
+    /// The kernel's build ID if present.
+    pub build_id: Option<Vec<u8>>,
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<LoadedKernel>();
+};
+
+impl LoadedKernel {
+    /// Returns the load information for the kernel, including its `R_*_IRELATIVE` relocations.
+    ///
+    /// `irelative_relocations` has to contain [`Self::irelative_relocations`],
+    /// copied to memory that the kernel can access.
+    pub fn load_info_with(
+        &self,
+        irelative_relocations: &'static [IRelativeRelocation],
+    ) -> LoadInfo {
+        LoadInfo {
+            kernel_image_addr_range: self.load_info.kernel_image_addr_range.clone(),
+            tls_info: self.load_info.tls_info,
+            initrds: self.load_info.initrds,
+            irelative_relocations,
+        }
+    }
+}
+
+/// A dynamic relocation of a [`KernelObject`].
+///
+/// See [`KernelObject::relocations`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// The architecture-specific relocation type.
+    pub r_type: u32,
+
+    /// The offset of the relocation target in the kernel image.
+    pub offset: u64,
+
+    /// The index of the referenced dynamic symbol or `0`.
+    ///
+    /// See [`KernelObject::symbol`].
+    pub sym: u32,
+
+    /// The addend or [`None`] if it is stored at the relocation target.
+    pub addend: Option<i64>,
+}
+
+/// A dynamic symbol of a [`KernelObject`].
+///
+/// See [`KernelObject::symbol`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// The symbol's value.
+    ///
+    /// For relocatable kernels, this is relative to the start address.
+    /// For TLS symbols, this is the offset in the TLS block.
+    pub value: u64,
+
+    /// The symbol's binding (`STB_*`).
+    pub bind: u8,
+
+    /// Whether the symbol is defined in the kernel.
+    pub defined: bool,
+}
+
+/// The destination memory of a kernel being loaded.
+///
+/// This allows loading a kernel into non-contiguous memory, such as separately mapped guest memory regions.
+/// Addresses are in the range of the kernel image as passed to [`KernelObject::load_kernel_into`].
+pub trait KernelSink {
+    /// Writes `bytes` to `addr`.
+    fn write(&mut self, addr: u64, bytes: &[u8]) -> Result<(), LoadKernelError>;
+
+    /// Writes `len` zero bytes to `addr`.
+    fn zero(&mut self, addr: u64, len: u64) -> Result<(), LoadKernelError>;
+
+    /// Reads `bytes.len()` bytes from `addr`.
+    ///
+    /// This is only used for memory that has already been written or zeroed.
+    fn read(&mut self, addr: u64, bytes: &mut [u8]) -> Result<(), LoadKernelError>;
+}
+
+/// A hook for applying relocations while loading a kernel.
+///
+/// Loaders can use this to log, rebase, or reject relocations.
+/// See [`KernelObject::load_kernel_with`].
+pub trait RelocationHandler {
+    /// Handles a single relocation.
+    ///
+    /// `sink` contains the loaded kernel at `start_addr`.
+    /// The default implementation calls [`KernelObject::apply_relocation`].
+    fn relocate(
+        &mut self,
+        kernel: &KernelObject<'_>,
+        sink: &mut dyn KernelSink,
+        start_addr: u64,
+        relocation: &Relocation,
+    ) -> Result<(), LoadKernelError> {
+        kernel.apply_relocation(sink, start_addr, relocation)
+    }
+}
+
+/// The [`RelocationHandler`] used by [`KernelObject::load_kernel`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultRelocationHandler;
+
+impl RelocationHandler for DefaultRelocationHandler {}
+
+#[cfg(test)]
+mod tests;
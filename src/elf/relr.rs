@@ -0,0 +1,93 @@
+//! Packed relative relocations (`DT_RELR`).
+//!
+//! See <https://groups.google.com/g/generic-abi/c/bX460iggiKg/m/YT2RrjpMAwAJ>.
+
+use core::mem;
+
+/// Dynamic tag for the total size of the RELR relocation table.
+pub(crate) const DT_RELRSZ: u64 = 35;
+
+/// Dynamic tag for the address of the RELR relocation table.
+pub(crate) const DT_RELR: u64 = 36;
+
+/// Dynamic tag for the size of one RELR relocation entry.
+pub(crate) const DT_RELRENT: u64 = 37;
+
+/// A RELR relocation entry.
+pub(crate) type Relr = u64;
+
+/// The number of bits of a bitmap entry that describe relocations.
+const BITMAP_BITS: u64 = Relr::BITS as u64 - 1;
+
+/// An iterator over the offsets of a RELR relocation table.
+#[derive(Clone)]
+pub(crate) struct RelrIter<'a> {
+    entries: core::slice::Iter<'a, Relr>,
+
+    /// The offset the next bitmap entry is relative to.
+    base: u64,
+
+    /// The offset the current bitmap is relative to.
+    bitmap_base: u64,
+
+    /// The remaining bits of the current bitmap.
+    bitmap: u64,
+}
+
+impl<'a> RelrIter<'a> {
+    pub(crate) fn new(entries: &'a [Relr]) -> Self {
+        Self {
+            entries: entries.iter(),
+            base: 0,
+            bitmap_base: 0,
+            bitmap: 0,
+        }
+    }
+}
+
+impl Iterator for RelrIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const WORD: u64 = mem::size_of::<Relr>() as u64;
+
+        loop {
+            if self.bitmap != 0 {
+                let bit = u64::from(self.bitmap.trailing_zeros());
+                self.bitmap &= self.bitmap - 1;
+                return Some(self.bitmap_base.wrapping_add(bit * WORD));
+            }
+
+            let entry = *self.entries.next()?;
+            if entry & 1 == 0 {
+                // An address entry relocates one word and starts the following bitmaps.
+                self.base = entry.wrapping_add(WORD);
+                return Some(entry);
+            }
+
+            // A bitmap entry relocates up to 63 words following the current base.
+            self.bitmap_base = self.base;
+            self.bitmap = entry >> 1;
+            self.base = self.base.wrapping_add(BITMAP_BITS * WORD);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let entries = [0x10000, 0b1001_1011, 1 | (1 << 63), 0x20000];
+        let offsets = RelrIter::new(&entries).collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            [
+                0x10000, 0x10008, 0x10018, 0x10020, 0x10038, 0x103f0, 0x20000
+            ]
+        );
+    }
+}
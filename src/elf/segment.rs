@@ -0,0 +1,98 @@
+use core::fmt;
+use core::ops::Range;
+
+use goblin::elf64::program_header::{self, ProgramHeader};
+
+/// A loadable segment (`PT_LOAD`) of a kernel object.
+///
+/// See [`KernelObject::segments`](super::KernelObject::segments).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// The virtual address range of the segment.
+    ///
+    /// For relocatable kernels, this is relative to the load address.
+    pub vaddr_range: Range<u64>,
+
+    /// The range of the segment's contents in the ELF file.
+    ///
+    /// The remainder of [`Self::vaddr_range`] is zeroed.
+    pub file_range: Range<u64>,
+
+    /// The alignment of the segment.
+    pub align: u64,
+
+    /// The memory access permissions of the segment.
+    pub flags: SegmentFlags,
+}
+
+impl Segment {
+    pub(crate) fn from_ph(ph: &ProgramHeader) -> Self {
+        Self {
+            vaddr_range: ph.p_vaddr..ph.p_vaddr + ph.p_memsz,
+            file_range: ph.p_offset..ph.p_offset + ph.p_filesz,
+            align: ph.p_align,
+            flags: SegmentFlags(ph.p_flags),
+        }
+    }
+
+    /// Moves the segment's virtual address range by `offset`.
+    pub(crate) fn offset(self, offset: u64) -> Self {
+        Self {
+            vaddr_range: self.vaddr_range.start + offset..self.vaddr_range.end + offset,
+            ..self
+        }
+    }
+}
+
+impl fmt::Debug for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            vaddr_range,
+            file_range,
+            align,
+            flags,
+        } = self;
+        f.debug_struct("Segment")
+            .field(
+                "vaddr_range",
+                &format_args!("{:#x}..{:#x}", vaddr_range.start, vaddr_range.end),
+            )
+            .field(
+                "file_range",
+                &format_args!("{:#x}..{:#x}", file_range.start, file_range.end),
+            )
+            .field("align", &format_args!("{align:#x}"))
+            .field("flags", flags)
+            .finish()
+    }
+}
+
+/// Memory access permissions of a segment (`p_flags`).
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SegmentFlags(pub u32);
+
+impl SegmentFlags {
+    /// Returns `true` if the segment is readable (`PF_R`).
+    pub const fn is_readable(self) -> bool {
+        self.0 & program_header::PF_R != 0
+    }
+
+    /// Returns `true` if the segment is writable (`PF_W`).
+    pub const fn is_writable(self) -> bool {
+        self.0 & program_header::PF_W != 0
+    }
+
+    /// Returns `true` if the segment is executable (`PF_X`).
+    pub const fn is_executable(self) -> bool {
+        self.0 & program_header::PF_X != 0
+    }
+}
+
+impl fmt::Debug for SegmentFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let r = if self.is_readable() { 'R' } else { '-' };
+        let w = if self.is_writable() { 'W' } else { '-' };
+        let x = if self.is_executable() { 'X' } else { '-' };
+        write!(f, "{r}{w}{x}")
+    }
+}
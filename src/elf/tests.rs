@@ -0,0 +1,1357 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use align_address::Align;
+use goblin::elf::note::Nhdr32;
+
+use super::*;
+use crate::Platforms;
+
+#[repr(C, align(8))]
+struct Aligned<const N: usize>([u8; N]);
+
+#[test]
+fn parse_truncated() {
+    let elf = Aligned([0; 16]);
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::OutOfBounds(ElfRegion::Header)
+    );
+    assert!(KernelObject::parse(&[]).is_err());
+}
+
+fn elf_header(arch: Arch) -> Aligned<{ mem::size_of::<Header>() }> {
+    let mut elf = Aligned([0; mem::size_of::<Header>()]);
+    let header = plain::from_mut_bytes::<Header>(&mut elf.0).unwrap();
+    header.e_ident[..header::SELFMAG].copy_from_slice(header::ELFMAG);
+    header.e_ident[header::EI_CLASS] = header::ELFCLASS64;
+    header.e_ident[header::EI_DATA] = header::ELFDATA2LSB;
+    header.e_type = header::ET_DYN;
+    header.e_machine = arch.e_machine();
+    elf
+}
+
+#[test]
+fn parse_out_of_bounds_program_headers() {
+    let mut elf = elf_header(Arch::HOST);
+    let header = plain::from_mut_bytes::<Header>(&mut elf.0).unwrap();
+    header.e_phoff = u64::MAX;
+    header.e_phentsize = mem::size_of::<ProgramHeader>() as u16;
+    header.e_phnum = 1;
+
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::OutOfBounds(ElfRegion::ProgramHeaders)
+    );
+}
+
+#[test]
+fn parse_misaligned_program_headers() {
+    let mut elf = elf_header(Arch::HOST);
+    let header = plain::from_mut_bytes::<Header>(&mut elf.0).unwrap();
+    header.e_phoff = 1;
+    header.e_phentsize = mem::size_of::<ProgramHeader>() as u16;
+    header.e_phnum = 1;
+
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::Misaligned(ElfRegion::ProgramHeaders)
+    );
+}
+
+#[test]
+fn parse_invalid_entry_size() {
+    let mut elf = elf_header(Arch::HOST);
+    let header = plain::from_mut_bytes::<Header>(&mut elf.0).unwrap();
+    header.e_phentsize = 1;
+    header.e_phnum = 1;
+
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::InvalidEntrySize(ElfRegion::ProgramHeaders)
+    );
+}
+
+#[test]
+fn parse_foreign_arch() {
+    let foreign = if Arch::HOST == Arch::Riscv64 {
+        Arch::Aarch64
+    } else {
+        Arch::Riscv64
+    };
+    let elf = elf_header(foreign);
+
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::WrongArchitecture {
+            found: foreign.e_machine(),
+            expected: Arch::HOST,
+        }
+    );
+
+    // The architecture check passes, but the header-only file has nothing to load.
+    let err = KernelObject::parse_any_arch(&elf.0).unwrap_err();
+    assert_eq!(err.kind(), &ParseKernelErrorKind::NoLoadableSegments);
+}
+
+/// A minimal relocatable kernel for testing.
+///
+/// The whole file is mapped by a single loadable segment at virtual address 0,
+/// so file offsets and virtual addresses coincide.
+#[derive(Default)]
+struct TestKernel {
+    relas: Vec<Rela>,
+    rels: Vec<Rel>,
+    relrs: Vec<Relr>,
+    dynsyms: Vec<Sym>,
+    data: Vec<u8>,
+    strip_section_headers: bool,
+
+    /// The contents of a second note segment.
+    extra_notes: Vec<u8>,
+
+    /// Omits the entry version note from the first note segment.
+    skip_entry_version: bool,
+
+    /// Additional program headers.
+    ///
+    /// Adding program headers moves [`TestElf::data_offset`].
+    extra_phs: Vec<ProgramHeader>,
+}
+
+/// A built [`TestKernel`].
+struct TestElf {
+    buf: Vec<u64>,
+    data_offset: u64,
+}
+
+impl TestElf {
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: `u64` can be viewed as bytes.
+        unsafe { core::slice::from_raw_parts(self.buf.as_ptr().cast(), self.buf.len() * 8) }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: `u64` can be viewed as bytes.
+        unsafe { core::slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.buf.len() * 8) }
+    }
+
+    fn ph_mut(&mut self, index: usize) -> &mut ProgramHeader {
+        let offset = mem::size_of::<Header>() + index * mem::size_of::<ProgramHeader>();
+        plain::from_mut_bytes(&mut self.bytes_mut()[offset..]).unwrap()
+    }
+
+    /// Returns the dynamic entry at `index`, in the order of [`TestKernel::build`].
+    fn dyn_mut(&mut self, index: usize) -> &mut Dyn {
+        let offset = self.ph_mut(3).p_offset as usize + index * mem::size_of::<Dyn>();
+        plain::from_mut_bytes(&mut self.bytes_mut()[offset..]).unwrap()
+    }
+}
+
+fn put<T: Plain>(elf: &mut [u8], offset: usize, value: T) {
+    *plain::from_mut_bytes::<T>(&mut elf[offset..]).unwrap() = value;
+}
+
+impl TestKernel {
+    const PHNUM: usize = 4;
+    const DYNNUM: usize = 13;
+
+    fn build(&self, arch: Arch) -> TestElf {
+        let phoff = mem::size_of::<Header>();
+        let phnum = Self::PHNUM + self.extra_phs.len();
+        let note_offset = phoff + phnum * mem::size_of::<ProgramHeader>();
+        let note_len = if self.skip_entry_version { 0 } else { 24 };
+        let extra_notes_offset = note_offset + note_len;
+        let data_offset = extra_notes_offset + self.extra_notes.len();
+        let dynamic_offset = (data_offset + self.data.len()).align_up(8);
+        let rela_offset = dynamic_offset + Self::DYNNUM * mem::size_of::<Dyn>();
+        let relasz = self.relas.len() * mem::size_of::<Rela>();
+        let rel_offset = rela_offset + relasz;
+        let relsz = self.rels.len() * mem::size_of::<Rel>();
+        let relr_offset = rel_offset + relsz;
+        let relrsz = self.relrs.len() * mem::size_of::<Relr>();
+        let dynsym_offset = relr_offset + relrsz;
+        let hash_offset = dynsym_offset + self.dynsyms.len() * mem::size_of::<Sym>();
+        let hash = [1, self.dynsyms.len() as u32, 0]
+            .into_iter()
+            .chain(self.dynsyms.iter().map(|_| 0))
+            .collect::<Vec<u32>>();
+        let shoff = (hash_offset + hash.len() * mem::size_of::<u32>()).align_up(8);
+        let dyns: [(u64, usize); Self::DYNNUM] = [
+            (dynamic::DT_RELA, rela_offset),
+            (dynamic::DT_RELASZ, relasz),
+            (dynamic::DT_RELAENT, mem::size_of::<Rela>()),
+            (dynamic::DT_REL, rel_offset),
+            (dynamic::DT_RELSZ, relsz),
+            (dynamic::DT_RELENT, mem::size_of::<Rel>()),
+            (relr::DT_RELR, relr_offset),
+            (relr::DT_RELRSZ, relrsz),
+            (relr::DT_RELRENT, mem::size_of::<Relr>()),
+            (dynamic::DT_SYMTAB, dynsym_offset),
+            (dynamic::DT_SYMENT, mem::size_of::<Sym>()),
+            (dynamic::DT_HASH, hash_offset),
+            (dynamic::DT_NULL, 0),
+        ];
+        let len = shoff + 2 * mem::size_of::<SectionHeader>();
+
+        let mut buf = vec![0u64; len.div_ceil(8)];
+        // SAFETY: `u64` can be viewed as bytes.
+        let elf = unsafe { core::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), len) };
+
+        let mut header = *plain::from_bytes::<Header>(&elf_header(arch).0).unwrap();
+        header.e_phoff = phoff as u64;
+        header.e_phentsize = mem::size_of::<ProgramHeader>() as u16;
+        header.e_phnum = phnum as u16;
+        if !self.strip_section_headers {
+            header.e_shoff = shoff as u64;
+            header.e_shentsize = mem::size_of::<SectionHeader>() as u16;
+            header.e_shnum = 2;
+        }
+        put(elf, 0, header);
+
+        let phs = [
+            ProgramHeader {
+                p_type: program_header::PT_LOAD,
+                p_flags: program_header::PF_R | program_header::PF_W,
+                p_offset: 0,
+                p_filesz: len as u64,
+                p_memsz: len as u64,
+                p_align: 8,
+                ..Default::default()
+            },
+            ProgramHeader {
+                p_type: program_header::PT_NOTE,
+                p_offset: note_offset as u64,
+                p_vaddr: note_offset as u64,
+                p_filesz: note_len as u64,
+                p_memsz: note_len as u64,
+                p_align: 4,
+                ..Default::default()
+            },
+            ProgramHeader {
+                p_type: program_header::PT_NOTE,
+                p_offset: extra_notes_offset as u64,
+                p_vaddr: extra_notes_offset as u64,
+                p_filesz: self.extra_notes.len() as u64,
+                p_memsz: self.extra_notes.len() as u64,
+                p_align: 4,
+                ..Default::default()
+            },
+            ProgramHeader {
+                p_type: program_header::PT_DYNAMIC,
+                p_offset: dynamic_offset as u64,
+                p_vaddr: dynamic_offset as u64,
+                p_filesz: (Self::DYNNUM * mem::size_of::<Dyn>()) as u64,
+                p_memsz: (Self::DYNNUM * mem::size_of::<Dyn>()) as u64,
+                p_align: 8,
+                ..Default::default()
+            },
+        ];
+        for (i, ph) in phs.into_iter().chain(self.extra_phs.clone()).enumerate() {
+            put(elf, phoff + i * mem::size_of::<ProgramHeader>(), ph);
+        }
+
+        if !self.skip_entry_version {
+            put(
+                elf,
+                note_offset,
+                Nhdr32 {
+                    n_namesz: 7,
+                    n_descsz: 1,
+                    n_type: crate::NT_HERMIT_ENTRY_VERSION,
+                },
+            );
+            elf[note_offset + 12..][..7].copy_from_slice(b"HERMIT\0");
+            elf[note_offset + 20] = crate::HERMIT_ENTRY_VERSION;
+        }
+
+        for (i, (d_tag, d_val)) in dyns.into_iter().enumerate() {
+            let d_val = d_val as u64;
+            put(
+                elf,
+                dynamic_offset + i * mem::size_of::<Dyn>(),
+                Dyn { d_tag, d_val },
+            );
+        }
+        for (i, rela) in self.relas.iter().enumerate() {
+            put(elf, rela_offset + i * mem::size_of::<Rela>(), *rela);
+        }
+        for (i, rel) in self.rels.iter().enumerate() {
+            put(elf, rel_offset + i * mem::size_of::<Rel>(), rel.clone());
+        }
+        for (i, relr) in self.relrs.iter().enumerate() {
+            put(elf, relr_offset + i * mem::size_of::<Relr>(), *relr);
+        }
+        for (i, sym) in self.dynsyms.iter().enumerate() {
+            put(elf, dynsym_offset + i * mem::size_of::<Sym>(), *sym);
+        }
+        for (i, word) in hash.into_iter().enumerate() {
+            put(elf, hash_offset + i * mem::size_of::<u32>(), word);
+        }
+        elf[extra_notes_offset..][..self.extra_notes.len()].copy_from_slice(&self.extra_notes);
+        elf[data_offset..][..self.data.len()].copy_from_slice(&self.data);
+
+        put(
+            elf,
+            shoff + mem::size_of::<SectionHeader>(),
+            SectionHeader {
+                sh_type: section_header::SHT_DYNSYM,
+                sh_offset: dynsym_offset as u64,
+                sh_size: (self.dynsyms.len() * mem::size_of::<Sym>()) as u64,
+                sh_entsize: mem::size_of::<Sym>() as u64,
+                ..Default::default()
+            },
+        );
+
+        TestElf {
+            buf,
+            data_offset: data_offset as u64,
+        }
+    }
+}
+
+/// Encodes a note with 4-byte alignment.
+fn note(ty: u32, name: &str, desc: &[u8]) -> Vec<u8> {
+    let mut note = Vec::new();
+    let n_namesz = name.len() as u32 + 1;
+    for word in [n_namesz, desc.len() as u32, ty] {
+        note.extend_from_slice(&word.to_ne_bytes());
+    }
+    note.extend_from_slice(name.as_bytes());
+    note.push(0);
+    note.resize(note.len().align_up(4), 0);
+    note.extend_from_slice(desc);
+    note.resize(note.len().align_up(4), 0);
+    note
+}
+
+fn load(kernel: &KernelObject<'_>, start_addr: u64) -> Result<Vec<u8>, LoadKernelError> {
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size()];
+    kernel.load_kernel(&mut memory, start_addr)?;
+    // SAFETY: `load_kernel` initializes the whole image.
+    Ok(memory
+        .into_iter()
+        .map(|byte| unsafe { byte.assume_init() })
+        .collect())
+}
+
+fn read_u64(memory: &[u8], offset: u64) -> u64 {
+    u64::from_ne_bytes(memory[offset as usize..][..8].try_into().unwrap())
+}
+
+fn rela(arch: Arch, r_offset: u64, kind: RelocationKind, sym: u32, r_addend: i64) -> Rela {
+    let r_type = (0..u32::MAX)
+        .find(|&r_type| arch.relocation_kind(r_type) == Some(kind))
+        .unwrap();
+    Rela {
+        r_offset,
+        r_info: reloc::r_info(u64::from(sym), u64::from(r_type)),
+        r_addend,
+    }
+}
+
+#[test]
+fn load_relocatable() {
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        dynsyms: vec![
+            Sym::default(),
+            Sym {
+                st_value: 0x1234,
+                st_shndx: 1,
+                ..Default::default()
+            },
+        ],
+        data: vec![0; 16],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![
+        rela(Arch::HOST, data_offset, RelocationKind::Relative, 0, 0x42),
+        rela(Arch::HOST, data_offset + 8, RelocationKind::Abs64, 1, 8),
+    ];
+    let elf = kernel.build(Arch::HOST);
+
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.start_addr(), None);
+    assert_eq!(
+        kernel.symbol(1),
+        Some(Symbol {
+            value: 0x1234,
+            bind: sym::STB_LOCAL,
+            defined: true,
+        })
+    );
+    assert_eq!(kernel.symbol(2), None);
+    let memory = load(&kernel, START).unwrap();
+    assert_eq!(read_u64(&memory, data_offset), START + 0x42);
+    assert_eq!(read_u64(&memory, data_offset + 8), START + 0x1234 + 8);
+}
+
+#[test]
+fn load_any_arch() {
+    const START: u64 = 0x20_0000;
+    for arch in [Arch::X86_64, Arch::Aarch64, Arch::Riscv64] {
+        let mut kernel = TestKernel {
+            dynsyms: vec![
+                Sym::default(),
+                Sym {
+                    st_value: 0x1234,
+                    st_shndx: 1,
+                    ..Default::default()
+                },
+            ],
+            data: vec![0; 24],
+            ..Default::default()
+        };
+        let data_offset = kernel.build(arch).data_offset;
+        kernel.relas = vec![
+            rela(arch, data_offset, RelocationKind::Relative, 0, 0x42),
+            rela(arch, data_offset + 8, RelocationKind::Abs64, 1, 8),
+            rela(arch, data_offset + 16, RelocationKind::JumpSlot, 1, 0),
+        ];
+        let elf = kernel.build(arch);
+
+        let kernel = KernelObject::parse_any_arch(elf.bytes()).unwrap();
+        assert_eq!(kernel.arch(), arch);
+        let memory = load(&kernel, START).unwrap();
+        assert_eq!(read_u64(&memory, data_offset), START + 0x42, "{arch}");
+        assert_eq!(
+            read_u64(&memory, data_offset + 8),
+            START + 0x1234 + 8,
+            "{arch}"
+        );
+        assert_eq!(
+            read_u64(&memory, data_offset + 16),
+            START + 0x1234,
+            "{arch}"
+        );
+    }
+}
+
+#[test]
+fn parse_big_endian() {
+    let mut elf = elf_header(Arch::HOST);
+    let header = plain::from_mut_bytes::<Header>(&mut elf.0).unwrap();
+    header.e_ident[header::EI_DATA] = header::ELFDATA2MSB;
+
+    let err = KernelObject::parse(&elf.0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::UnsupportedDataEncoding(header::ELFDATA2MSB)
+    );
+}
+
+#[test]
+fn load_out_of_bounds_relocation() {
+    let kernel = TestKernel {
+        relas: vec![rela(
+            Arch::HOST,
+            u64::MAX - 4,
+            RelocationKind::Relative,
+            0,
+            0,
+        )],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let err = load(&kernel, 0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::RelocationOutOfBounds {
+            offset: u64::MAX - 4
+        }
+    );
+}
+
+#[test]
+fn load_wrong_memory_size() {
+    let elf = TestKernel::default().build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size() - 1];
+    let err = kernel.load_kernel(&mut memory, 0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::WrongMemorySize {
+            found: kernel.mem_size() - 1,
+            expected: kernel.mem_size(),
+        }
+    );
+}
+
+#[test]
+fn load_wrong_start_address() {
+    let mut elf = TestKernel::default().build(Arch::HOST);
+    plain::from_mut_bytes::<Header>(elf.bytes_mut())
+        .unwrap()
+        .e_type = header::ET_EXEC;
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.start_addr(), Some(0));
+
+    let err = load(&kernel, 0x20_0000).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::WrongStartAddress {
+            found: 0x20_0000,
+            expected: 0,
+        }
+    );
+}
+
+#[test]
+fn load_segment_out_of_bounds() {
+    // The image ends with the last loadable segment, which ends before the first one.
+    let kernel = TestKernel {
+        extra_phs: vec![ProgramHeader {
+            p_type: program_header::PT_LOAD,
+            p_vaddr: 0x10,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let err = load(&kernel, 0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::SegmentOutOfBounds { segment: 0 }
+    );
+}
+
+#[test]
+fn unsupported_relocation() {
+    const R_TYPE: u32 = 0xffff;
+    let unsupported = Rela {
+        r_offset: 0,
+        r_info: reloc::r_info(0, u64::from(R_TYPE)),
+        r_addend: 0,
+    };
+    let kernel = TestKernel {
+        relas: vec![unsupported],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::UnsupportedRelocation { r_type: R_TYPE }
+    );
+
+    // Handlers may pass relocations that were not validated by `KernelObject::parse`.
+    struct Unsupported;
+
+    impl RelocationHandler for Unsupported {
+        fn relocate(
+            &mut self,
+            kernel: &KernelObject<'_>,
+            sink: &mut dyn KernelSink,
+            start_addr: u64,
+            relocation: &Relocation,
+        ) -> Result<(), LoadKernelError> {
+            let relocation = Relocation {
+                r_type: R_TYPE,
+                ..*relocation
+            };
+            kernel.apply_relocation(sink, start_addr, &relocation)
+        }
+    }
+
+    let kernel = TestKernel {
+        relas: vec![rela(Arch::HOST, 0, RelocationKind::Relative, 0, 0)],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size()];
+    let err = kernel
+        .load_kernel_with(&mut memory, 0, &mut Unsupported)
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::UnsupportedRelocation { r_type: R_TYPE }
+    );
+}
+
+#[test]
+fn load_invalid_symbol_relocations() {
+    let weak_undefined = Sym {
+        st_info: STB_WEAK << 4,
+        st_shndx: SHN_UNDEF as u16,
+        ..Default::default()
+    };
+    let defined = Sym {
+        st_value: 0x1234,
+        st_shndx: 1,
+        ..Default::default()
+    };
+    let load_relocation = |arch, kind, sym, addend| {
+        let mut kernel = TestKernel {
+            dynsyms: vec![Sym::default(), weak_undefined, defined],
+            data: vec![0; 8],
+            ..Default::default()
+        };
+        let data_offset = kernel.build(arch).data_offset;
+        kernel.relas = vec![rela(arch, data_offset, kind, sym, addend)];
+        let elf = kernel.build(arch);
+        let kernel = KernelObject::parse_any_arch(elf.bytes()).unwrap();
+        (data_offset, load(&kernel, 0).unwrap_err().kind().clone())
+    };
+
+    let (_, err) = load_relocation(Arch::HOST, RelocationKind::Abs64, 3, 0);
+    assert_eq!(err, LoadKernelErrorKind::SymbolOutOfBounds { index: 3 });
+
+    // Without an addend in the relocation, the addend is read from the target.
+    let mut kernel = TestKernel {
+        dynsyms: vec![Sym::default(), weak_undefined],
+        data: vec![1, 0, 0, 0, 0, 0, 0, 0],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    let r_type = (0..u32::MAX)
+        .find(|&r_type| Arch::HOST.relocation_kind(r_type) == Some(RelocationKind::Abs64))
+        .unwrap();
+    kernel.rels = vec![Rel {
+        r_offset: data_offset,
+        r_info: reloc::r_info(1, u64::from(r_type)),
+    }];
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let err = load(&kernel, 0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::UnresolvedWeakSymbol {
+            offset: data_offset
+        }
+    );
+
+    let (offset, err) = load_relocation(Arch::X86_64, RelocationKind::GlobDat, 2, 8);
+    assert_eq!(err, LoadKernelErrorKind::UnexpectedAddend { offset });
+}
+
+#[test]
+fn load_relr() {
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        data: [0x10u64, 0x20, 0x30, 0x40]
+            .into_iter()
+            .flat_map(u64::to_ne_bytes)
+            .collect(),
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relrs = vec![data_offset, 0b1011];
+    let elf = kernel.build(Arch::HOST);
+
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let memory = load(&kernel, START).unwrap();
+    assert_eq!(read_u64(&memory, data_offset), START + 0x10);
+    assert_eq!(read_u64(&memory, data_offset + 8), START + 0x20);
+    assert_eq!(read_u64(&memory, data_offset + 16), 0x30);
+    assert_eq!(read_u64(&memory, data_offset + 24), START + 0x40);
+}
+
+#[test]
+fn load_rel() {
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        dynsyms: vec![
+            Sym::default(),
+            Sym {
+                st_value: 0x1234,
+                st_shndx: 1,
+                ..Default::default()
+            },
+        ],
+        data: [0x10u64, 0x20]
+            .into_iter()
+            .flat_map(u64::to_ne_bytes)
+            .collect(),
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.rels = [
+        rela(Arch::HOST, data_offset, RelocationKind::Relative, 0, 0),
+        rela(Arch::HOST, data_offset + 8, RelocationKind::Abs64, 1, 0),
+    ]
+    .into_iter()
+    .map(|rela| Rel {
+        r_offset: rela.r_offset,
+        r_info: rela.r_info,
+    })
+    .collect();
+    let elf = kernel.build(Arch::HOST);
+
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let memory = load(&kernel, START).unwrap();
+    assert_eq!(read_u64(&memory, data_offset), START + 0x10);
+    assert_eq!(read_u64(&memory, data_offset + 8), START + 0x1234 + 0x20);
+}
+
+#[test]
+fn load_without_section_headers() {
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        dynsyms: vec![
+            Sym::default(),
+            Sym {
+                st_value: 0x1234,
+                st_shndx: 1,
+                ..Default::default()
+            },
+        ],
+        data: vec![0; 8],
+        strip_section_headers: true,
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![rela(Arch::HOST, data_offset, RelocationKind::GlobDat, 1, 0)];
+    let elf = kernel.build(Arch::HOST);
+
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.dynsyms.len(), 2);
+    let memory = load(&kernel, START).unwrap();
+    assert_eq!(read_u64(&memory, data_offset), START + 0x1234);
+}
+
+#[test]
+fn load_irelative() {
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        data: vec![0; 8],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![rela(
+        Arch::HOST,
+        data_offset,
+        RelocationKind::IRelative,
+        0,
+        0x42,
+    )];
+    let elf = kernel.build(Arch::HOST);
+
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size()];
+    let loaded = kernel.load_kernel(&mut memory, START).unwrap();
+    assert_eq!(loaded.entry_version, crate::HERMIT_ENTRY_VERSION);
+    assert_eq!(loaded.build_id, None);
+    assert_eq!(loaded.segments.len(), 1);
+    assert_eq!(loaded.segments[0].vaddr_range.start, START);
+    assert_eq!(
+        loaded.irelative_relocations,
+        [IRelativeRelocation {
+            addr: START + data_offset,
+            resolver: START + 0x42,
+        }]
+    );
+}
+
+#[cfg(feature = "kernel")]
+#[test]
+fn irelative_boot_info() {
+    use crate::boot_info::{Arguments, BootInfo, HardwareInfo, PlatformInfo, RawBootInfo};
+
+    let mut kernel = TestKernel {
+        data: vec![0; 8],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![rela(
+        Arch::HOST,
+        data_offset,
+        RelocationKind::IRelative,
+        0,
+        0x42,
+    )];
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size()];
+    let loaded = kernel.load_kernel(&mut memory, 0x20_0000).unwrap();
+
+    let irelative_relocations = loaded.irelative_relocations.clone().leak();
+    let raw_boot_info = RawBootInfo::from(BootInfo {
+        hardware_info: HardwareInfo {
+            phys_addr_range: 0..0x1000_0000,
+            serial_port_base: None,
+            device_tree: None,
+            memory_regions: &[],
+        },
+        load_info: loaded.load_info_with(irelative_relocations),
+        platform_info: PlatformInfo::Fdt,
+        arguments: Arguments::default(),
+    });
+    let boot_info =
+        BootInfo::try_from(unsafe { RawBootInfo::read(&raw_boot_info) }.unwrap()).unwrap();
+    assert_eq!(
+        boot_info.load_info.irelative_relocations,
+        loaded.irelative_relocations
+    );
+}
+
+#[test]
+fn load_tls() {
+    let mut kernel = TestKernel {
+        data: vec![0; 16],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![
+        rela(Arch::HOST, data_offset, RelocationKind::TlsDtpMod, 0, 0),
+        rela(Arch::HOST, data_offset + 8, RelocationKind::TlsTpOff, 0, 0),
+    ];
+    let elf = kernel.build(Arch::HOST);
+
+    // The test kernel does not have a TLS segment.
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let err = load(&kernel, 0).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::MissingTls {
+            offset: data_offset + 8
+        }
+    );
+}
+
+/// Returns a `PT_TLS` program header for `len` bytes at `offset`.
+fn tls_ph(offset: u64, len: u64) -> ProgramHeader {
+    ProgramHeader {
+        p_type: program_header::PT_TLS,
+        p_offset: offset,
+        p_vaddr: offset,
+        p_filesz: len,
+        p_memsz: len,
+        p_align: 8,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parse_invalid_tls() {
+    let mut kernel = TestKernel {
+        data: vec![0; 16],
+        extra_phs: vec![ProgramHeader::default()],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    let segment = TestKernel::PHNUM;
+
+    kernel.extra_phs = vec![ProgramHeader {
+        p_memsz: u64::MAX,
+        ..tls_ph(data_offset, 16)
+    }];
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::SegmentAddressOverflow { segment }
+    );
+
+    kernel.extra_phs = vec![tls_ph(elf.bytes().len() as u64, 16)];
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::SegmentOutsideImage { segment }
+    );
+
+    kernel.extra_phs = vec![ProgramHeader {
+        p_align: 3,
+        ..tls_ph(data_offset, 16)
+    }];
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::InvalidTlsAlignment { segment }
+    );
+}
+
+#[test]
+fn load_tls_values() {
+    for arch in [Arch::X86_64, Arch::Aarch64, Arch::Riscv64] {
+        // The TLS segment covers the first 16 bytes of data, followed by the relocation targets.
+        let mut kernel = TestKernel {
+            data: vec![0; 40],
+            dynsyms: vec![
+                Sym::default(),
+                Sym {
+                    st_info: sym::STT_TLS,
+                    st_value: 8,
+                    ..Default::default()
+                },
+            ],
+            extra_phs: vec![ProgramHeader::default()],
+            ..Default::default()
+        };
+        let data_offset = kernel.build(arch).data_offset;
+        kernel.extra_phs = vec![tls_ph(data_offset, 16)];
+        kernel.relas = vec![
+            rela(arch, data_offset + 16, RelocationKind::TlsDtpMod, 1, 0),
+            rela(arch, data_offset + 24, RelocationKind::TlsDtpOff, 1, 4),
+            rela(arch, data_offset + 32, RelocationKind::TlsTpOff, 1, 4),
+        ];
+        let elf = kernel.build(arch);
+
+        let kernel = KernelObject::parse_any_arch(elf.bytes()).unwrap();
+        let memory = load(&kernel, 0x20_0000).unwrap();
+        let (dtpoff, tpoff) = match arch {
+            // Variant II: the TLS block ends at the thread pointer.
+            Arch::X86_64 => (12, 12 - 16),
+            // Variant I with a 16-byte TCB.
+            Arch::Aarch64 => (12, 12 + 16),
+            // Variant I without a TCB, with DTV offsets biased by 0x800.
+            Arch::Riscv64 => (12 - 0x800, 12),
+        };
+        assert_eq!(read_u64(&memory, data_offset + 16), 1, "{arch}");
+        assert_eq!(read_u64(&memory, data_offset + 24), dtpoff as u64, "{arch}");
+        assert_eq!(read_u64(&memory, data_offset + 32), tpoff as u64, "{arch}");
+    }
+}
+
+#[test]
+fn load_with_handler() {
+    struct RejectAbs64(Vec<Relocation>);
+
+    impl RelocationHandler for RejectAbs64 {
+        fn relocate(
+            &mut self,
+            kernel: &KernelObject<'_>,
+            sink: &mut dyn KernelSink,
+            start_addr: u64,
+            relocation: &Relocation,
+        ) -> Result<(), LoadKernelError> {
+            self.0.push(*relocation);
+            if kernel.arch.relocation_kind(relocation.r_type) == Some(RelocationKind::Abs64) {
+                let offset = relocation.offset;
+                return Err(LoadKernelErrorKind::RejectedRelocation { offset }.into());
+            }
+            kernel.apply_relocation(sink, start_addr, relocation)
+        }
+    }
+
+    let mut kernel = TestKernel {
+        dynsyms: vec![Sym::default(), Sym::default()],
+        data: vec![0; 16],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.relas = vec![
+        rela(Arch::HOST, data_offset, RelocationKind::Relative, 0, 0x42),
+        rela(Arch::HOST, data_offset + 8, RelocationKind::Abs64, 1, 0),
+    ];
+    kernel.relrs = vec![data_offset];
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let relocations = kernel.relocations().collect::<Vec<_>>();
+    assert_eq!(relocations.len(), 3);
+    assert_eq!(relocations[0].addend, Some(0x42));
+    assert_eq!(relocations[2].offset, data_offset);
+    assert_eq!(relocations[2].addend, None);
+
+    let mut handler = RejectAbs64(Vec::new());
+    let mut memory = vec![MaybeUninit::uninit(); kernel.mem_size()];
+    let err = kernel
+        .load_kernel_with(&mut memory, 0, &mut handler)
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &LoadKernelErrorKind::RejectedRelocation {
+            offset: data_offset + 8
+        }
+    );
+    assert_eq!(handler.0, relocations[..2]);
+}
+
+#[test]
+fn load_into_pages() {
+    /// A sink that stores the image in small, separately allocated pages.
+    struct PageSink {
+        start_addr: u64,
+        pages: Vec<[u8; Self::PAGE_SIZE]>,
+    }
+
+    impl PageSink {
+        const PAGE_SIZE: usize = 64;
+
+        fn byte(&mut self, addr: u64) -> Result<&mut u8, LoadKernelError> {
+            let offset = (addr - self.start_addr) as usize;
+            self.pages
+                .get_mut(offset / Self::PAGE_SIZE)
+                .map(|page| &mut page[offset % Self::PAGE_SIZE])
+                .ok_or(LoadKernelErrorKind::InaccessibleMemory { addr }.into())
+        }
+    }
+
+    impl KernelSink for PageSink {
+        fn write(&mut self, addr: u64, bytes: &[u8]) -> Result<(), LoadKernelError> {
+            for (addr, byte) in (addr..).zip(bytes) {
+                *self.byte(addr)? = *byte;
+            }
+            Ok(())
+        }
+
+        fn zero(&mut self, addr: u64, len: u64) -> Result<(), LoadKernelError> {
+            for addr in addr..addr + len {
+                *self.byte(addr)? = 0;
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, addr: u64, bytes: &mut [u8]) -> Result<(), LoadKernelError> {
+            for (addr, byte) in (addr..).zip(bytes) {
+                *byte = *self.byte(addr)?;
+            }
+            Ok(())
+        }
+    }
+
+    const START: u64 = 0x20_0000;
+    let mut kernel = TestKernel {
+        data: 0x10u64.to_ne_bytes().to_vec(),
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    kernel.rels = vec![Rel {
+        r_offset: data_offset,
+        r_info: rela(Arch::HOST, 0, RelocationKind::Relative, 0, 0).r_info,
+    }];
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let mut sink = PageSink {
+        start_addr: START,
+        pages: vec![[0xff; PageSink::PAGE_SIZE]; kernel.mem_size().div_ceil(PageSink::PAGE_SIZE)],
+    };
+    kernel
+        .load_kernel_into(&mut sink, START, &mut DefaultRelocationHandler)
+        .unwrap();
+    let memory = sink.pages.concat();
+    assert_eq!(memory[..kernel.mem_size()], load(&kernel, START).unwrap());
+    assert_eq!(read_u64(&memory, data_offset), START + 0x10);
+}
+
+#[test]
+fn segments() {
+    let elf = TestKernel::default().build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let len = elf.bytes().len() as u64;
+    let segments = kernel.segments().collect::<Vec<_>>();
+    assert_eq!(
+        segments,
+        [Segment {
+            vaddr_range: 0..len,
+            file_range: 0..len,
+            align: 8,
+            flags: SegmentFlags(program_header::PF_R | program_header::PF_W),
+        }]
+    );
+    assert!(segments[0].flags.is_writable());
+    assert!(!segments[0].flags.is_executable());
+    assert_eq!(kernel.relro(), None);
+    assert_eq!(kernel.stack_flags(), None);
+}
+
+#[test]
+fn relro_and_stack() {
+    let mut kernel = TestKernel {
+        data: vec![0; 32],
+        extra_phs: vec![ProgramHeader::default(); 2],
+        ..Default::default()
+    };
+    let data_offset = kernel.build(Arch::HOST).data_offset;
+    let relro = ProgramHeader {
+        p_type: program_header::PT_GNU_RELRO,
+        p_flags: program_header::PF_R,
+        p_offset: data_offset,
+        p_vaddr: data_offset,
+        p_filesz: 16,
+        p_memsz: 16,
+        p_align: 1,
+        ..Default::default()
+    };
+    let stack = ProgramHeader {
+        p_type: program_header::PT_GNU_STACK,
+        p_flags: program_header::PF_R | program_header::PF_W,
+        ..Default::default()
+    };
+    kernel.extra_phs = vec![relro, stack];
+    let elf = kernel.build(Arch::HOST);
+
+    let object = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(object.relro(), Some(data_offset..data_offset + 16));
+    let stack_flags = object.stack_flags().unwrap();
+    assert!(stack_flags.is_readable());
+    assert!(stack_flags.is_writable());
+    assert!(!stack_flags.is_executable());
+
+    kernel.extra_phs = vec![
+        ProgramHeader {
+            p_vaddr: u64::MAX,
+            ..relro
+        },
+        stack,
+    ];
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::SegmentAddressOverflow {
+            segment: TestKernel::PHNUM
+        }
+    );
+}
+
+#[test]
+fn choose_start_addr() {
+    let elf = TestKernel::default().build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.max_align(), 8);
+
+    let size = kernel.mem_size() as u64;
+    let windows = [0x1000..0x1000 + 2 * size + 8, 0..0x100];
+    let reserved = [0x1000..0x1008, 0..0x100];
+    for random in 0..16 {
+        let start_addr = kernel
+            .choose_start_addr(&windows, &reserved, random)
+            .unwrap();
+        assert_eq!(start_addr % 8, 0);
+        assert!(start_addr >= 0x1008);
+        assert!(start_addr + size <= windows[0].end);
+    }
+    assert_eq!(kernel.choose_start_addr(&windows, &windows, 0), None);
+}
+
+#[test]
+fn notes() {
+    let build_id = [0xde, 0xad, 0xbe, 0xef, 0x42];
+    let kernel = TestKernel {
+        extra_notes: [
+            note(0x1234, "Other", &[1, 2, 3]),
+            note(note::NT_GNU_BUILD_ID, "GNU", &build_id),
+        ]
+        .concat(),
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+
+    let notes = kernel.notes().collect::<Vec<_>>();
+    assert_eq!(notes.len(), 3);
+    assert_eq!(notes[0].name, "HERMIT");
+    assert_eq!(
+        notes[1],
+        Note {
+            ty: 0x1234,
+            name: "Other",
+            desc: &[1, 2, 3],
+        }
+    );
+    assert_eq!(kernel.build_id(), Some(&build_id[..]));
+    assert_eq!(kernel.find_note("Other", 0x1234), Some(&[1, 2, 3][..]));
+    assert_eq!(kernel.find_note("Other", 0x1235), None);
+}
+
+#[test]
+fn parse_invalid_segments() {
+    let load_ph = |vaddr, filesz, memsz| ProgramHeader {
+        p_type: program_header::PT_LOAD,
+        p_vaddr: vaddr,
+        p_filesz: filesz,
+        p_memsz: memsz,
+        ..Default::default()
+    };
+    let segment = TestKernel::PHNUM;
+
+    let kernel = TestKernel {
+        extra_phs: vec![load_ph(0x1000, 8, 0)],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::SegmentFileSizeExceedsMemSize { segment }
+    );
+
+    let kernel = TestKernel {
+        extra_phs: vec![load_ph(0x1000, 0, 0), load_ph(0x800, 0, 0)],
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::UnsortedSegments {
+            segment: segment + 1
+        }
+    );
+}
+
+#[test]
+fn parse_invalid_notes() {
+    let parse_note = |note: Vec<u8>| {
+        let kernel = TestKernel {
+            extra_notes: note,
+            ..Default::default()
+        };
+        let elf = kernel.build(Arch::HOST);
+        KernelObject::parse(elf.bytes()).unwrap_err().kind().clone()
+    };
+
+    let mut not_utf8 = note(0x1234, "Other", &[]);
+    not_utf8[12] = 0xff;
+    assert_eq!(parse_note(not_utf8), ParseKernelErrorKind::NoteNameNotUtf8);
+
+    let mut not_nul_terminated = note(0x1234, "Other", &[]);
+    not_nul_terminated[12 + 5] = b'!';
+    assert_eq!(
+        parse_note(not_nul_terminated),
+        ParseKernelErrorKind::NoteNameNotNulTerminated
+    );
+
+    let mut out_of_bounds = note(0x1234, "Other", &[]);
+    out_of_bounds[4..8].copy_from_slice(&0x100u32.to_ne_bytes());
+    assert_eq!(
+        parse_note(out_of_bounds),
+        ParseKernelErrorKind::OutOfBounds(ElfRegion::Note)
+    );
+}
+
+#[test]
+fn parse_no_note_segment() {
+    let mut elf = TestKernel::default().build(Arch::HOST);
+    elf.ph_mut(1).p_type = program_header::PT_NULL;
+    elf.ph_mut(2).p_type = program_header::PT_NULL;
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(err.kind(), &ParseKernelErrorKind::NoNoteSegment);
+}
+
+#[test]
+fn parse_dynamically_linked() {
+    let mut elf = TestKernel::default().build(Arch::HOST);
+    // Replace `DT_NULL`.
+    *elf.dyn_mut(TestKernel::DYNNUM - 1) = Dyn {
+        d_tag: dynamic::DT_NEEDED,
+        d_val: 0,
+    };
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(err.kind(), &ParseKernelErrorKind::DynamicallyLinked);
+}
+
+#[test]
+fn parse_unmapped_relocations() {
+    let kernel = TestKernel {
+        relas: vec![rela(Arch::HOST, 0, RelocationKind::Relative, 0, 0)],
+        ..Default::default()
+    };
+    let mut elf = kernel.build(Arch::HOST);
+    // `DT_RELA`
+    elf.dyn_mut(0).d_val = 0x10_0000;
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(err.kind(), &ParseKernelErrorKind::Unmapped(ElfRegion::Rela));
+}
+
+#[test]
+fn padded_notes() {
+    let kernel = TestKernel {
+        extra_notes: [note(0x1234, "Other", &[1, 2, 3]), vec![0; 8]].concat(),
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.notes().count(), 2);
+}
+
+#[test]
+fn parse_entry_version_in_second_note_segment() {
+    let mut kernel = TestKernel {
+        skip_entry_version: true,
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(err.kind(), &ParseKernelErrorKind::MissingEntryVersion);
+
+    kernel.extra_notes = note(
+        crate::NT_HERMIT_ENTRY_VERSION,
+        "HERMIT",
+        &[crate::HERMIT_ENTRY_VERSION],
+    );
+    let elf = kernel.build(Arch::HOST);
+    KernelObject::parse(elf.bytes()).unwrap();
+
+    kernel.extra_notes = note(crate::NT_HERMIT_ENTRY_VERSION, "HERMIT", &[3]);
+    let elf = kernel.build(Arch::HOST);
+    let kernel_object = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel_object.entry_version(), 3);
+
+    kernel.extra_notes = note(crate::NT_HERMIT_ENTRY_VERSION, "HERMIT", &[1]);
+    let elf = kernel.build(Arch::HOST);
+    let err = KernelObject::parse(elf.bytes()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParseKernelErrorKind::EntryVersionMismatch {
+            found: 1,
+            supported: SUPPORTED_ENTRY_VERSIONS,
+        }
+    );
+}
+
+#[test]
+fn requirements() {
+    let elf = TestKernel::default().build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel.requirements(), None);
+
+    let desc = [
+        &(64u64 << 20).to_le_bytes()[..],
+        &2u32.to_le_bytes(),
+        &(Platforms::UHYVE | Platforms::FDT).0.to_le_bytes(),
+    ]
+    .concat();
+    let kernel = TestKernel {
+        extra_notes: note(crate::NT_HERMIT_REQUIREMENTS, "HERMIT", &desc),
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel = KernelObject::parse(elf.bytes()).unwrap();
+    let requirements = kernel.requirements().unwrap();
+    assert_eq!(requirements.memory, 64 << 20);
+    assert_eq!(requirements.cpus, 2);
+    assert!(requirements.platforms.contains(Platforms::UHYVE));
+    assert!(!requirements.platforms.contains(Platforms::MULTIBOOT));
+}
+
+#[test]
+fn default_cmdline() {
+    let mut kernel = TestKernel {
+        extra_notes: note(
+            crate::NT_HERMIT_DEFAULT_CMDLINE,
+            "HERMIT",
+            b"-freq 1000 -- --verbose",
+        ),
+        ..Default::default()
+    };
+    let elf = kernel.build(Arch::HOST);
+    let kernel_object = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(
+        kernel_object.default_cmdline(),
+        Some("-freq 1000 -- --verbose")
+    );
+
+    kernel.extra_notes = note(crate::NT_HERMIT_DEFAULT_CMDLINE, "HERMIT", &[0xff]);
+    let elf = kernel.build(Arch::HOST);
+    let kernel_object = KernelObject::parse(elf.bytes()).unwrap();
+    assert_eq!(kernel_object.default_cmdline(), None);
+}
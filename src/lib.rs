@@ -19,18 +19,22 @@ pub mod config;
 #[cfg(feature = "loader")]
 pub mod elf;
 
-#[cfg(feature = "kernel")]
+#[cfg(any(feature = "loader", feature = "kernel"))]
 mod note;
 
 use core::error::Error;
-use core::fmt;
 use core::str::FromStr;
+use core::{fmt, ops};
 
 #[doc(hidden)]
 pub use const_parse::parse_u128 as _parse_u128;
+#[cfg(any(feature = "loader", feature = "kernel"))]
+pub use note::Note;
 #[cfg(feature = "kernel")]
 #[doc(hidden)]
-pub use note::{_AbiTag, _Note};
+pub use note::{_Note, _note_name_len};
+#[cfg(feature = "kernel")]
+pub use note::{KernelNotes, kernel_notes};
 
 /// GZIP magic number.
 ///
@@ -68,10 +72,15 @@ pub fn detect_format(data: &[u8]) -> Option<Format> {
 ///
 /// `cpu_id` is the number of the CPU core with the boot processor being number 0.
 ///
+/// `raw_boot_info` may point to a smaller [`RawBootInfo`] from an older loader.
+/// Kernels read it using `RawBootInfo::read`.
+///
 /// The stack pointer has to be valid for the boot processor only.
+///
+/// [`RawBootInfo`]: boot_info::RawBootInfo
 #[cfg(not(target_arch = "riscv64"))]
 pub type Entry =
-    unsafe extern "C" fn(raw_boot_info: &'static boot_info::RawBootInfo, cpu_id: u32) -> !;
+    unsafe extern "C" fn(raw_boot_info: *const boot_info::RawBootInfo, cpu_id: u32) -> !;
 
 /// Kernel entry point.
 ///
@@ -79,10 +88,15 @@ pub type Entry =
 ///
 /// `hart_id` is the number of the hardware thread.
 ///
+/// `raw_boot_info` may point to a smaller [`RawBootInfo`] from an older loader.
+/// Kernels read it using `RawBootInfo::read`.
+///
 /// The stack pointer has to be valid for the boot processor only.
+///
+/// [`RawBootInfo`]: boot_info::RawBootInfo
 #[cfg(target_arch = "riscv64")]
 pub type Entry =
-    unsafe extern "C" fn(hart_id: usize, raw_boot_info: &'static boot_info::RawBootInfo) -> !;
+    unsafe extern "C" fn(hart_id: usize, raw_boot_info: *const boot_info::RawBootInfo) -> !;
 
 /// Note type for specifying the hermit entry version.
 ///
@@ -94,7 +108,23 @@ const NT_HERMIT_ENTRY_VERSION: u32 = 0x5a00;
 
 /// The current hermit entry version.
 #[cfg_attr(not(any(feature = "loader", feature = "kernel")), expect(dead_code))]
-const HERMIT_ENTRY_VERSION: u8 = 4;
+const HERMIT_ENTRY_VERSION: u8 = 5;
+
+/// Note type for specifying the kernel's requirements.
+///
+/// The note name for this is `HERMIT`.
+///
+/// The `desc` field will be 16 bytes, which specify the [`KernelRequirements`].
+#[cfg_attr(not(any(feature = "loader", feature = "kernel")), expect(dead_code))]
+const NT_HERMIT_REQUIREMENTS: u32 = 0x5a01;
+
+/// Note type for specifying the kernel's default command line.
+///
+/// The note name for this is `HERMIT`.
+///
+/// The `desc` field will be the UTF-8 encoded command line.
+#[cfg_attr(not(any(feature = "loader", feature = "kernel")), expect(dead_code))]
+const NT_HERMIT_DEFAULT_CMDLINE: u32 = 0x5a02;
 
 /// Note type for specifying the Uhyve interface version in an elf header.
 #[cfg_attr(not(any(feature = "loader", feature = "kernel")), expect(dead_code))]
@@ -201,6 +231,111 @@ impl fmt::Display for UhyveIfVersion {
     }
 }
 
+/// Minimal requirements for a kernel to be able to run as expected.
+///
+/// Kernels declare these using `define_requirements!`.
+/// Loaders read them using `KernelObject::requirements`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KernelRequirements {
+    /// Minimum RAM in bytes.
+    pub memory: u64,
+
+    /// Minimum amount of CPUs.
+    pub cpus: u32,
+
+    /// The platforms the kernel supports.
+    pub platforms: Platforms,
+}
+
+impl KernelRequirements {
+    /// Encodes the requirements for [`NT_HERMIT_REQUIREMENTS`].
+    #[cfg(feature = "kernel")]
+    pub(crate) const fn to_bytes(self) -> [u8; 16] {
+        let memory = self.memory.to_le_bytes();
+        let cpus = self.cpus.to_le_bytes();
+        let platforms = self.platforms.0.to_le_bytes();
+        [
+            memory[0],
+            memory[1],
+            memory[2],
+            memory[3],
+            memory[4],
+            memory[5],
+            memory[6],
+            memory[7],
+            cpus[0],
+            cpus[1],
+            cpus[2],
+            cpus[3],
+            platforms[0],
+            platforms[1],
+            platforms[2],
+            platforms[3],
+        ]
+    }
+
+    /// Decodes the requirements from [`NT_HERMIT_REQUIREMENTS`].
+    #[cfg(any(feature = "loader", feature = "kernel"))]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes = <&[u8; 16]>::try_from(bytes).ok()?;
+        let (memory, rest) = bytes.split_at(8);
+        let (cpus, platforms) = rest.split_at(4);
+        Some(Self {
+            memory: u64::from_le_bytes(memory.try_into().unwrap()),
+            cpus: u32::from_le_bytes(cpus.try_into().unwrap()),
+            platforms: Platforms(u32::from_le_bytes(platforms.try_into().unwrap())),
+        })
+    }
+}
+
+/// A set of platforms.
+///
+/// These correspond to the variants of [`PlatformInfo`](boot_info::PlatformInfo).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Platforms(u32);
+
+impl Platforms {
+    /// Uhyve.
+    pub const UHYVE: Self = Self(1 << 0);
+
+    /// Multiboot.
+    pub const MULTIBOOT: Self = Self(1 << 1);
+
+    /// Direct Linux Boot.
+    pub const LINUX_BOOT: Self = Self(1 << 2);
+
+    /// Linux boot params ("zeropage").
+    pub const LINUX_BOOT_PARAMS: Self = Self(1 << 3);
+
+    /// FDT.
+    pub const FDT: Self = Self(1 << 4);
+
+    /// All platforms.
+    pub const ALL: Self = Self::UHYVE
+        .union(Self::MULTIBOOT)
+        .union(Self::LINUX_BOOT)
+        .union(Self::LINUX_BOOT_PARAMS)
+        .union(Self::FDT);
+
+    /// Returns the set of both `self` and `other`.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns `true` if all platforms in `other` are contained in `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ops::BitOr for Platforms {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,517 @@
+use core::slice;
+
+use super::{Note, NoteIterator};
+use crate::boot_info::LoadInfo;
+use crate::{HermitVersion, KernelRequirements, UhyveIfVersion};
+
+/// Defines the hermit entry version in the note section.
+///
+/// This macro must be used in a module that is guaranteed to be linked.
+/// See <https://github.com/rust-lang/rust/issues/99721>.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_entry_version {
+    () => {
+        #[used]
+        #[unsafe(link_section = ".note.hermit.entry-version")]
+        static ENTRY_VERSION: $crate::_Note<8, 1> = $crate::_Note::entry_version();
+    };
+}
+
+/// Defines the Uhyve interface version in the note section.
+///
+/// This macro must be used in a module that is guaranteed to be linked.
+/// See <https://github.com/rust-lang/rust/issues/99721>.
+///
+/// # Examples
+///
+/// ```
+/// # mod uhyve_interface {
+/// #     pub const UHYVE_INTERFACE_VERSION: u32 = 1;
+/// # }
+/// #
+/// hermit_entry::define_uhyve_interface_version!(uhyve_interface::UHYVE_INTERFACE_VERSION);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_uhyve_interface_version {
+    ($version:expr) => {
+        #[used]
+        #[unsafe(link_section = ".note.hermit.uhyve-interface-version")]
+        static INTERFACE_VERSION: $crate::_Note<8, 4> = $crate::_Note::uhyveif_version($version);
+    };
+}
+
+/// Defines the kernel's [`KernelRequirements`] in the note section.
+///
+/// Loaders can refuse to boot a kernel whose requirements are not met.
+///
+/// This macro must be used in a module that is guaranteed to be linked.
+/// See <https://github.com/rust-lang/rust/issues/99721>.
+///
+/// # Examples
+///
+/// ```
+/// use hermit_entry::{KernelRequirements, Platforms};
+///
+/// hermit_entry::define_requirements!(KernelRequirements {
+///     memory: 32 * 1024 * 1024,
+///     cpus: 1,
+///     platforms: Platforms::UHYVE.union(Platforms::FDT),
+/// });
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_requirements {
+    ($requirements:expr) => {
+        #[used]
+        #[unsafe(link_section = ".note.hermit.requirements")]
+        static REQUIREMENTS: $crate::_Note<8, 16> = $crate::_Note::requirements($requirements);
+    };
+}
+
+/// Defines the kernel's default command line in the note section.
+///
+/// Loaders can merge it with user-supplied arguments.
+///
+/// This macro must be used in a module that is guaranteed to be linked.
+/// See <https://github.com/rust-lang/rust/issues/99721>.
+///
+/// # Examples
+///
+/// ```
+/// hermit_entry::define_default_cmdline!("-freq 1000 -- --verbose");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_default_cmdline {
+    ($cmdline:expr) => {
+        #[used]
+        #[unsafe(link_section = ".note.hermit.default-cmdline")]
+        static DEFAULT_CMDLINE: $crate::_Note<8, { $cmdline.len() }> =
+            $crate::_Note::default_cmdline($cmdline);
+    };
+}
+
+/// Defines a custom note in the note section.
+///
+/// The note is identified by its name and type and carries arbitrary bytes as descriptor.
+/// Loaders can look it up using `KernelObject::find_note`.
+///
+/// This macro must be used in a module that is guaranteed to be linked.
+/// See <https://github.com/rust-lang/rust/issues/99721>.
+///
+/// # Examples
+///
+/// ```
+/// const NT_GIT_COMMIT: u32 = 1;
+///
+/// hermit_entry::define_note!(GIT_COMMIT, "MYKERNEL", NT_GIT_COMMIT, b"0123abcd");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_note {
+    ($ident:ident, $name:expr, $ty:expr, $desc:expr) => {
+        #[used]
+        #[unsafe(link_section = ".note.hermit")]
+        static $ident: $crate::_Note<{ $crate::_note_name_len($name) }, { $desc.len() }> =
+            $crate::_Note::new($name, $ty, $desc);
+    };
+}
+
+/// Returns the size of a note's name including the NUL terminator and padding.
+#[doc(hidden)]
+pub const fn _note_name_len(name: &str) -> usize {
+    (name.len() + 1).next_multiple_of(4)
+}
+
+/// An ELF note with a padded name of `N` bytes and a descriptor of `D` bytes.
+#[repr(C)]
+#[doc(hidden)]
+pub struct _Note<const N: usize, const D: usize> {
+    header: Nhdr32,
+    name: [u8; N],
+    desc: [u8; D],
+}
+
+impl<const N: usize, const D: usize> _Note<N, D> {
+    pub const fn new(name: &str, n_type: u32, desc: &[u8]) -> Self {
+        assert!(N == _note_name_len(name), "wrong note name size");
+        assert!(D == desc.len(), "wrong note descriptor size");
+        let name = name.as_bytes();
+
+        let mut name_buf = [0; N];
+        let mut i = 0;
+        while i < name.len() {
+            name_buf[i] = name[i];
+            i += 1;
+        }
+
+        let mut desc_buf = [0; D];
+        let mut i = 0;
+        while i < desc.len() {
+            desc_buf[i] = desc[i];
+            i += 1;
+        }
+
+        Self {
+            header: Nhdr32 {
+                n_namesz: name.len() as u32 + 1,
+                n_descsz: D as u32,
+                n_type,
+            },
+            name: name_buf,
+            desc: desc_buf,
+        }
+    }
+}
+
+impl _Note<8, 1> {
+    pub const fn entry_version() -> Self {
+        Self::new(
+            "HERMIT",
+            crate::NT_HERMIT_ENTRY_VERSION,
+            &[crate::HERMIT_ENTRY_VERSION],
+        )
+    }
+}
+
+impl<const D: usize> _Note<8, D> {
+    pub const fn default_cmdline(cmdline: &str) -> Self {
+        Self::new(
+            "HERMIT",
+            crate::NT_HERMIT_DEFAULT_CMDLINE,
+            cmdline.as_bytes(),
+        )
+    }
+}
+
+impl _Note<8, 4> {
+    pub const fn uhyveif_version(ver: u32) -> Self {
+        Self::new(
+            "UHYVEIF",
+            crate::NT_UHYVE_INTERFACE_VERSION,
+            &ver.to_be_bytes(),
+        )
+    }
+}
+
+impl _Note<8, 16> {
+    pub const fn requirements(requirements: KernelRequirements) -> Self {
+        Self::new(
+            "HERMIT",
+            crate::NT_HERMIT_REQUIREMENTS,
+            &requirements.to_bytes(),
+        )
+    }
+}
+
+#[repr(C)]
+struct Nhdr32 {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+/// Defines the current Hermit kernel version in the note section.
+///
+/// The version is saved in `.note.ABI-tag` in accordance with [LSB].
+///
+/// [LSB]: https://refspecs.linuxfoundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/noteabitag.html
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[macro_export]
+macro_rules! define_abi_tag {
+    () => {
+        #[used]
+        #[unsafe(link_section = ".note.ABI-tag")]
+        static ABI_TAG: $crate::_Note<4, 16> = $crate::_Note::abi_tag($crate::HermitVersion {
+            major: $crate::_parse_u128(::core::env!("CARGO_PKG_VERSION_MAJOR")) as u32,
+            minor: $crate::_parse_u128(::core::env!("CARGO_PKG_VERSION_MINOR")) as u32,
+            patch: $crate::_parse_u128(::core::env!("CARGO_PKG_VERSION_PATCH")) as u32,
+        });
+    };
+}
+
+impl _Note<4, 16> {
+    pub const fn abi_tag(version: HermitVersion) -> Self {
+        let data = [
+            crate::ELF_NOTE_OS_HERMIT,
+            version.major,
+            version.minor,
+            version.patch,
+        ];
+
+        let mut desc = [0; 16];
+        let mut i = 0;
+        while i < desc.len() {
+            desc[i] = data[i / 4].to_ne_bytes()[i % 4];
+            i += 1;
+        }
+
+        Self::new("GNU", crate::NT_GNU_ABI_TAG, &desc)
+    }
+}
+
+/// ELF program header type of loadable segments.
+const PT_LOAD: u32 = 1;
+
+/// ELF program header type of note segments.
+const PT_NOTE: u32 = 4;
+
+/// The size of an ELF64 program header.
+const PHENTSIZE: usize = 56;
+
+/// Reads `N` bytes at `offset`.
+fn read<const N: usize>(bytes: &[u8], offset: usize) -> Option<[u8; N]> {
+    bytes.get(offset..)?.get(..N)?.try_into().ok()
+}
+
+/// The fields of an ELF64 program header needed for finding notes.
+struct ProgramHeader {
+    p_type: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_filesz: u64,
+    p_align: u64,
+}
+
+impl ProgramHeader {
+    fn parse(bytes: &[u8]) -> Self {
+        let u64_at = |offset| u64::from_ne_bytes(read(bytes, offset).unwrap());
+        Self {
+            p_type: u32::from_ne_bytes(read(bytes, 0).unwrap()),
+            p_offset: u64_at(8),
+            p_vaddr: u64_at(16),
+            p_filesz: u64_at(32),
+            p_align: u64_at(48),
+        }
+    }
+}
+
+/// Returns the notes of the running kernel.
+///
+/// The notes are read from the kernel's loaded note segments.
+/// This requires the ELF header and program headers to be part of the first loadable segment.
+/// Returns [`None`] if they cannot be found.
+///
+/// # Safety
+///
+/// `load_info` must describe the running kernel and the kernel image must be readable.
+///
+/// # Examples
+///
+/// ```no_run
+/// use hermit_entry::boot_info::LoadInfo;
+///
+/// fn hermit_version(load_info: &LoadInfo) -> Option<hermit_entry::HermitVersion> {
+///     unsafe { hermit_entry::kernel_notes(load_info) }?.hermit_version()
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub unsafe fn kernel_notes(load_info: &LoadInfo) -> Option<KernelNotes> {
+    let range = &load_info.kernel_image_addr_range;
+    let len = usize::try_from(range.end.checked_sub(range.start)?).ok()?;
+    let image = unsafe { slice::from_raw_parts(range.start as *const u8, len) };
+    KernelNotes::new(image)
+}
+
+/// An iterator over the notes of the running kernel.
+///
+/// See [`kernel_notes`].
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone)]
+pub struct KernelNotes {
+    /// The loaded kernel image.
+    image: &'static [u8],
+
+    /// The virtual address of the first loadable segment, which starts at [`Self::image`].
+    image_vaddr: u64,
+
+    /// The remaining program headers.
+    phs: slice::ChunksExact<'static, u8>,
+
+    /// The remaining notes of the current note segment.
+    notes: NoteIterator<'static>,
+}
+
+impl KernelNotes {
+    fn new(image: &'static [u8]) -> Option<Self> {
+        if image.get(..4)? != b"\x7fELF" {
+            return None;
+        }
+
+        let phoff = usize::try_from(u64::from_ne_bytes(read(image, 32)?)).ok()?;
+        let phentsize = usize::from(u16::from_ne_bytes(read(image, 54)?));
+        let phnum = usize::from(u16::from_ne_bytes(read(image, 56)?));
+        if phentsize != PHENTSIZE {
+            return None;
+        }
+        let phs = image
+            .get(phoff..)?
+            .get(..phnum * PHENTSIZE)?
+            .chunks_exact(PHENTSIZE);
+
+        let first_load = phs
+            .clone()
+            .map(ProgramHeader::parse)
+            .find(|ph| ph.p_type == PT_LOAD)?;
+        if first_load.p_offset != 0 {
+            return None;
+        }
+
+        Some(Self {
+            image,
+            image_vaddr: first_load.p_vaddr,
+            phs,
+            notes: NoteIterator::new(&[], 1),
+        })
+    }
+
+    /// Returns the hermit entry version of the kernel if present.
+    pub fn entry_version(&self) -> Option<u8> {
+        self.clone().find_map(|note| note.entry_version()).flatten()
+    }
+
+    /// Returns the Hermit version of the kernel if present.
+    pub fn hermit_version(&self) -> Option<HermitVersion> {
+        self.clone().find_map(HermitVersion::from_note)
+    }
+
+    /// Returns the Uhyve interface version of the kernel if present.
+    pub fn uhyve_interface_version(&self) -> Option<UhyveIfVersion> {
+        self.clone().find_map(UhyveIfVersion::from_note)
+    }
+
+    /// Returns the requirements declared by the kernel if present.
+    pub fn requirements(&self) -> Option<KernelRequirements> {
+        self.clone().find_map(KernelRequirements::from_note)
+    }
+
+    /// Returns the default command line of the kernel if present and valid UTF-8.
+    pub fn default_cmdline(&self) -> Option<&'static str> {
+        self.clone()
+            .find_map(|note| note.default_cmdline())
+            .and_then(Result::ok)
+    }
+
+    /// Returns the descriptor of the first note with the given name and type.
+    ///
+    /// This can be used to read notes defined by `define_note!`.
+    pub fn find_note(&self, name: &str, ty: u32) -> Option<&'static [u8]> {
+        self.clone()
+            .find(|note| note.name == name && note.ty == ty)
+            .map(|note| note.desc)
+    }
+}
+
+impl Iterator for KernelNotes {
+    type Item = Note<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Malformed notes end their note segment.
+            if let Some(note) = self.notes.find_map(Result::ok) {
+                return Some(note);
+            }
+
+            let ph = self
+                .phs
+                .by_ref()
+                .map(ProgramHeader::parse)
+                .find(|ph| ph.p_type == PT_NOTE)?;
+            let align = ph.p_align.max(1);
+            let bytes = ph
+                .p_vaddr
+                .checked_sub(self.image_vaddr)
+                .and_then(|offset| usize::try_from(offset).ok())
+                .and_then(|offset| self.image.get(offset..))
+                .and_then(|bytes| bytes.get(..usize::try_from(ph.p_filesz).ok()?));
+            if let Some(bytes) = bytes
+                && align.is_power_of_two()
+            {
+                self.notes = NoteIterator::new(bytes, align as usize);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::mem;
+
+    use super::*;
+
+    /// Builds an image with the ELF header, program headers and a note segment.
+    fn image(notes: &[_Note<8, 1>], note_vaddr: u64) -> &'static [u8] {
+        const PHOFF: usize = 64;
+        const NOTE_OFFSET: usize = PHOFF + 2 * PHENTSIZE;
+        let notes_size = mem::size_of_val(notes);
+
+        let mut image = Vec::new();
+        image.extend_from_slice(b"\x7fELF");
+        image.resize(PHOFF, 0);
+        image[32..40].copy_from_slice(&(PHOFF as u64).to_ne_bytes());
+        image[54..56].copy_from_slice(&(PHENTSIZE as u16).to_ne_bytes());
+        image[56..58].copy_from_slice(&2u16.to_ne_bytes());
+
+        let load_vaddr = 0x20_0000;
+        for (p_type, p_offset, p_vaddr, p_filesz, p_align) in [
+            (PT_LOAD, 0, load_vaddr, NOTE_OFFSET + notes_size, 0x1000),
+            (PT_NOTE, NOTE_OFFSET, note_vaddr, notes_size, 4),
+        ] {
+            let mut ph = [0; PHENTSIZE];
+            ph[0..4].copy_from_slice(&p_type.to_ne_bytes());
+            ph[8..16].copy_from_slice(&(p_offset as u64).to_ne_bytes());
+            ph[16..24].copy_from_slice(&p_vaddr.to_ne_bytes());
+            ph[32..40].copy_from_slice(&(p_filesz as u64).to_ne_bytes());
+            ph[48..56].copy_from_slice(&(p_align as u64).to_ne_bytes());
+            image.extend_from_slice(&ph);
+        }
+
+        for note in notes {
+            let bytes = unsafe {
+                slice::from_raw_parts(
+                    (note as *const _Note<8, 1>).cast::<u8>(),
+                    mem::size_of::<_Note<8, 1>>(),
+                )
+            };
+            image.extend_from_slice(bytes);
+        }
+
+        Box::leak(image.into_boxed_slice())
+    }
+
+    #[test]
+    fn kernel_notes() {
+        let note_vaddr = 0x20_0000 + 64 + 2 * PHENTSIZE as u64;
+        let image = image(
+            &[_Note::new("HERMIT", 0x1234, &[5]), _Note::entry_version()],
+            note_vaddr,
+        );
+        let load_info = LoadInfo {
+            kernel_image_addr_range: image.as_ptr() as u64
+                ..image.as_ptr() as u64 + image.len() as u64,
+            tls_info: None,
+            initrds: &[],
+            irelative_relocations: &[],
+        };
+
+        let notes = unsafe { super::kernel_notes(&load_info) }.unwrap();
+        assert_eq!(notes.clone().count(), 2);
+        assert_eq!(notes.entry_version(), Some(crate::HERMIT_ENTRY_VERSION));
+        assert_eq!(notes.find_note("HERMIT", 0x1234), Some(&[5][..]));
+        assert_eq!(notes.hermit_version(), None);
+        assert_eq!(notes.default_cmdline(), None);
+    }
+
+    #[test]
+    fn kernel_notes_out_of_image() {
+        let image = image(&[_Note::entry_version()], 0x10_0000);
+        assert_eq!(KernelNotes::new(image).unwrap().count(), 0);
+        assert!(KernelNotes::new(&image[1..]).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! ELF notes of Hermit kernels.
+//!
+//! Kernels define notes using the `define_*!` macros and can read them back at runtime.
+//! Loaders read them through [`KernelObject`](crate::elf::KernelObject).
+
+#[cfg(feature = "kernel")]
+mod kernel;
+
+use core::{mem, str};
+
+use align_address::Align;
+
+#[cfg(feature = "kernel")]
+pub use self::kernel::{_Note, _note_name_len, KernelNotes, kernel_notes};
+use crate::{HermitVersion, KernelRequirements, UhyveIfVersion};
+
+/// An ELF note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Note<'a> {
+    /// The type of the note.
+    ///
+    /// The meaning of the type depends on [`Self::name`].
+    pub ty: u32,
+
+    /// The name of the note's originator without the NUL terminator.
+    pub name: &'a str,
+
+    /// The note's descriptor.
+    pub desc: &'a [u8],
+}
+
+/// An error from parsing a note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NoteError {
+    /// The note exceeds its note segment.
+    OutOfBounds,
+
+    /// The note's name is not NUL-terminated.
+    NameNotNulTerminated,
+
+    /// The note's name is not valid UTF-8.
+    NameNotUtf8,
+}
+
+/// An iterator over the notes of a note segment.
+///
+/// The iterator stops after the first error.
+/// Trailing bytes that are too short for a note header are treated as padding.
+#[derive(Clone)]
+pub(crate) struct NoteIterator<'a> {
+    bytes: &'a [u8],
+    align: usize,
+}
+
+impl<'a> NoteIterator<'a> {
+    /// The size of `n_namesz`, `n_descsz`, and `n_type`.
+    const HEADER_SIZE: usize = 3 * mem::size_of::<u32>();
+
+    pub(crate) fn new(bytes: &'a [u8], align: usize) -> Self {
+        Self { bytes, align }
+    }
+
+    fn parse_note(&mut self) -> Result<Note<'a>, NoteError> {
+        let [n_namesz, n_descsz, n_type] = [0, 1, 2].map(|i| {
+            let offset = i * mem::size_of::<u32>();
+            self.bytes
+                .get(offset..offset + mem::size_of::<u32>())
+                .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+        });
+        let (Some(n_namesz), Some(n_descsz), Some(n_type)) = (n_namesz, n_descsz, n_type) else {
+            return Err(NoteError::OutOfBounds);
+        };
+        let mut offset = Self::HEADER_SIZE;
+
+        let name = self
+            .bytes
+            .get(offset..)
+            .and_then(|bytes| bytes.get(..n_namesz as usize))
+            .ok_or(NoteError::OutOfBounds)?;
+        let name = match name.split_last() {
+            Some((0, name)) => name,
+            Some(_) => return Err(NoteError::NameNotNulTerminated),
+            None => &[],
+        };
+        let name = str::from_utf8(name).map_err(|_| NoteError::NameNotUtf8)?;
+        offset = (offset + n_namesz as usize).align_up(self.align);
+
+        let desc = self
+            .bytes
+            .get(offset..)
+            .and_then(|bytes| bytes.get(..n_descsz as usize))
+            .ok_or(NoteError::OutOfBounds)?;
+        offset = (offset + n_descsz as usize).align_up(self.align);
+
+        self.bytes = self.bytes.get(offset..).unwrap_or_default();
+        Ok(Note {
+            ty: n_type,
+            name,
+            desc,
+        })
+    }
+}
+
+impl<'a> Iterator for NoteIterator<'a> {
+    type Item = Result<Note<'a>, NoteError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.len() < Self::HEADER_SIZE {
+            return None;
+        }
+
+        let note = self.parse_note();
+        if note.is_err() {
+            self.bytes = &[];
+        }
+        Some(note)
+    }
+}
+
+impl Note<'_> {
+    /// Returns the hermit entry version if this is an entry version note.
+    pub(crate) fn entry_version(&self) -> Option<Option<u8>> {
+        (self.name == "HERMIT" && self.ty == crate::NT_HERMIT_ENTRY_VERSION)
+            .then(|| self.desc.first().copied())
+    }
+}
+
+impl<'a> Note<'a> {
+    /// Returns the command line if this is a default command line note.
+    pub(crate) fn default_cmdline(&self) -> Option<Result<&'a str, str::Utf8Error>> {
+        (self.name == "HERMIT" && self.ty == crate::NT_HERMIT_DEFAULT_CMDLINE)
+            .then(|| str::from_utf8(self.desc))
+    }
+}
+
+impl HermitVersion {
+    pub(crate) fn from_note(note: Note<'_>) -> Option<Self> {
+        if note.name != "GNU" {
+            return None;
+        }
+
+        if note.ty != crate::NT_GNU_ABI_TAG {
+            return None;
+        }
+
+        let data = <[u8; 16]>::try_from(note.desc).ok()?;
+        let data = unsafe { mem::transmute::<[u8; 16], [u32; 4]>(data) };
+
+        if data[0] != crate::ELF_NOTE_OS_HERMIT {
+            return None;
+        }
+
+        Some(Self {
+            major: data[1],
+            minor: data[2],
+            patch: data[3],
+        })
+    }
+}
+
+impl KernelRequirements {
+    pub(crate) fn from_note(note: Note<'_>) -> Option<Self> {
+        if note.name != "HERMIT" {
+            return None;
+        }
+
+        if note.ty != crate::NT_HERMIT_REQUIREMENTS {
+            return None;
+        }
+
+        Self::from_bytes(note.desc)
+    }
+}
+
+impl UhyveIfVersion {
+    pub(crate) fn from_note(note: Note<'_>) -> Option<Self> {
+        if note.name != "UHYVEIF" {
+            return None;
+        }
+
+        if note.ty != crate::NT_UHYVE_INTERFACE_VERSION {
+            return None;
+        }
+
+        let data = <[u8; 4]>::try_from(note.desc).ok()?;
+        let data = u32::from_be_bytes(data);
+
+        Some(Self(data))
+    }
+}
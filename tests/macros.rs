@@ -4,3 +4,10 @@
 hermit_entry::define_abi_tag!();
 hermit_entry::define_entry_version!();
 hermit_entry::define_uhyve_interface_version!(1);
+hermit_entry::define_requirements!(hermit_entry::KernelRequirements {
+    memory: 32 * 1024 * 1024,
+    cpus: 1,
+    platforms: hermit_entry::Platforms::UHYVE,
+});
+hermit_entry::define_note!(GIT_COMMIT, "MYKERNEL", 1, b"0123abcd");
+hermit_entry::define_default_cmdline!("-freq 1000 -- --verbose");